@@ -0,0 +1,150 @@
+/// Alarms at a time of the day that repeat in some days of the week
+use crate::datetime::{DateTime, DayOfWeek};
+use crate::ui::{Key, Msg};
+
+/// how many alarms can be configured
+pub const MAX_ALARMS: usize = 2;
+/// the time that a snoozed alarm waits to ring again
+pub const SNOOZE_SECONDS: u32 = 5 * 60;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Alarm {
+    pub hour: u8,
+    pub min: u8,
+    /// the days of the week that the alarm rings, the bit 0 is Monday
+    pub days: u8,
+    pub enabled: bool,
+}
+
+impl Alarm {
+    pub const EVERY_DAY: u8 = 0b111_1111;
+    pub const WEEKDAYS: u8 = 0b001_1111;
+
+    pub fn new(hour: u8, min: u8, days: u8) -> Self {
+        Self {
+            hour: hour.min(23),
+            min: min.min(59),
+            days: days & Self::EVERY_DAY,
+            enabled: true,
+        }
+    }
+
+    /// check if the alarm rings in the `day` of the week
+    pub fn rings_on(&self, day: DayOfWeek) -> bool {
+        self.days & (1 << day as u8) != 0
+    }
+
+    pub fn toggle_day(&mut self, day: DayOfWeek) {
+        self.days ^= 1 << day as u8;
+    }
+
+    /// the first time after `now` that the alarm rings, both in local time
+    pub fn next_after(&self, now: &DateTime) -> Option<DateTime> {
+        if !self.enabled {
+            return None;
+        }
+        let now_epoch = now.to_epoch()?;
+        // one week later is the same day of the week so a week and a day is enough
+        (0..=7).find_map(|days| {
+            let day = now.add_days(days)?;
+            let epoch = DateTime {
+                hour: self.hour,
+                min: self.min,
+                sec: 0,
+                ..day
+            }
+            .to_epoch()?;
+            let candidate = DateTime::new(epoch);
+            (epoch > now_epoch && self.rings_on(candidate.day_of_week)).then_some(candidate)
+        })
+    }
+
+    /// pack the alarm in two backup registers, zero is a disabled alarm
+    pub fn to_bits(self) -> [u16; 2] {
+        let time = u16::from(self.hour) << 8 | u16::from(self.min);
+        let flags = u16::from(self.enabled) << 7 | u16::from(self.days);
+        [time, flags]
+    }
+
+    pub fn from_bits(bits: [u16; 2]) -> Self {
+        let [time, flags] = bits;
+        Self {
+            enabled: flags & (1 << 7) != 0,
+            ..Self::new((time >> 8) as u8, time as u8, flags as u8)
+        }
+    }
+}
+
+/// the first time after `now` that any of the `alarms` rings
+pub fn next_alarm(alarms: &[Alarm], now: &DateTime) -> Option<DateTime> {
+    alarms
+        .iter()
+        .filter_map(|alarm| alarm.next_after(now))
+        .min_by_key(|time| time.to_epoch())
+}
+
+/// What the buttons do with an alarm that is ringing
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AlarmAction {
+    /// Enter, the alarm rings again the next scheduled day
+    Stop,
+    /// Up or Down, the alarm rings again after `SNOOZE_SECONDS`
+    Snooze,
+}
+
+impl AlarmAction {
+    pub fn from_msg(msg: Msg) -> Option<Self> {
+        match msg {
+            Msg::Enter | Msg::LongPress(Key::Enter) => Some(Self::Stop),
+            Msg::Up | Msg::Down | Msg::LongPress(Key::Up | Key::Down) => Some(Self::Snooze),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_next_alarm() {
+        // 2024-03-29 07:30:00 Friday
+        let now = DateTime::new(1711697400);
+        assert_eq!(now.day_of_week, DayOfWeek::Friday);
+        let weekdays = Alarm::new(7, 30, Alarm::WEEKDAYS);
+        // the current minute already rang so the next one is the monday
+        let next = weekdays.next_after(&now).unwrap();
+        assert_eq!((next.day, next.hour, next.min), (1, 7, 30));
+        assert_eq!(next.day_of_week, DayOfWeek::Monday);
+        let mut sunday = Alarm::new(9, 0, 0);
+        sunday.toggle_day(DayOfWeek::Sunday);
+        assert!(sunday.rings_on(DayOfWeek::Sunday));
+        assert_eq!(next_alarm(&[weekdays, sunday], &now).unwrap().day, 31);
+        // the same day of the next week
+        let friday = Alarm::new(7, 0, 1 << DayOfWeek::Friday as u8);
+        assert_eq!(friday.next_after(&now).unwrap().day, 5);
+        // disabled or without days never rings
+        assert_eq!(Alarm::new(8, 0, 0).next_after(&now), None);
+        assert_eq!(Alarm::default().next_after(&now), None);
+        assert_eq!(next_alarm(&[], &now), None);
+    }
+
+    #[test]
+    fn test_alarm_bits() {
+        assert_eq!(Alarm::from_bits([0, 0]), Alarm::default());
+        let alarm = Alarm::new(23, 59, Alarm::WEEKDAYS);
+        assert_eq!(Alarm::from_bits(alarm.to_bits()), alarm);
+        let disabled = Alarm {
+            enabled: false,
+            ..alarm
+        };
+        assert_eq!(Alarm::from_bits(disabled.to_bits()), disabled);
+    }
+
+    #[test]
+    fn test_alarm_action() {
+        assert_eq!(AlarmAction::from_msg(Msg::Enter), Some(AlarmAction::Stop));
+        assert_eq!(AlarmAction::from_msg(Msg::Down), Some(AlarmAction::Snooze));
+        assert_eq!(AlarmAction::from_msg(Msg::Tick), None);
+    }
+}
@@ -0,0 +1,304 @@
+/// The state machine of the menu and its screens, the firmware and the simulator drive it with
+/// the same messages and apply its `Effect`s to their own clock and storage
+use core::fmt::Write;
+
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+use heapless::String;
+
+use crate::alarm::{Alarm, AlarmAction, MAX_ALARMS};
+use crate::datetime::{DateFormat, DateTime};
+use crate::diagnostics::Diagnostics;
+use crate::screens::{draw_alarm, draw_clock, AlarmEditor, ClockEditor, TimeZoneEditor};
+use crate::timers::{Countdown, CountdownState, Millis, Stopwatch};
+use crate::timezone::TimeZone;
+use crate::ui::{self, MenuFSM, MenuState, Msg, Screen};
+
+/// What the application must do after a message, the `AppFSM` already changed its own state
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Effect {
+    /// the ringing alarm was stopped or snoozed
+    Alarm(AlarmAction),
+    /// the new time of the clock in UTC seconds
+    SetTime(u32),
+    /// the new time zone, the alarms are in the local time so they change too
+    SetTimeZone(TimeZone),
+    /// the alarm with this index changed
+    SetAlarm(usize, Alarm),
+    /// the finished countdown was reset or its screen closed so it stops ringing
+    CountdownReset,
+}
+
+/// The menu, the editors of the screens and the values that they edit
+pub struct AppFSM {
+    pub menu_fsm: MenuFSM,
+    pub timezone: TimeZone,
+    /// the alarms in the local time of the `timezone`
+    pub alarms: [Alarm; MAX_ALARMS],
+    clock_editor: ClockEditor,
+    timezone_editor: TimeZoneEditor,
+    alarm_editor: AlarmEditor,
+    countdown: Countdown,
+    /// the countdown finished behind the alarm screen, its screen is shown after the alarm
+    countdown_pending: bool,
+    stopwatch: Stopwatch,
+}
+
+impl AppFSM {
+    /// start in the main menu, `now` is the local time
+    pub fn new(now: DateTime, timezone: TimeZone, alarms: [Alarm; MAX_ALARMS]) -> Self {
+        Self {
+            menu_fsm: MenuFSM::init(MenuState::menu(ui::MAIN_MENU)),
+            timezone,
+            alarms,
+            clock_editor: ClockEditor::new(now),
+            timezone_editor: TimeZoneEditor::new(timezone),
+            alarm_editor: AlarmEditor::new(0, Alarm::default()),
+            countdown: Countdown::default(),
+            countdown_pending: false,
+            stopwatch: Stopwatch::new(),
+        }
+    }
+
+    /// check the end of the countdown, it can finish in any screen so its screen is shown, or
+    /// queued behind a ringing alarm. Returns true only once when it finishes
+    pub fn poll(&mut self, millis: Millis) -> bool {
+        let finished = self.countdown.poll(millis);
+        if finished && self.menu_fsm.state == MenuState::Screen(Screen::Alarm) {
+            self.countdown_pending = true;
+        } else if finished {
+            self.menu_fsm.show(Screen::Countdown);
+        }
+        finished
+    }
+
+    /// the current screen changes with the time, the ticks redraw it
+    pub fn is_animated(&self) -> bool {
+        matches!(
+            self.menu_fsm.state,
+            MenuState::Screen(
+                Screen::Clock
+                    | Screen::Alarm
+                    | Screen::Countdown
+                    | Screen::Stopwatch
+                    | Screen::Diagnostics
+            )
+        )
+    }
+
+    /// a timer runs in the current screen and needs the redraws of the monotonic clock
+    pub fn is_running(&self) -> bool {
+        match self.menu_fsm.state {
+            MenuState::Screen(Screen::Countdown) => self.countdown.is_running(),
+            MenuState::Screen(Screen::Stopwatch) => self.stopwatch.is_running(),
+            _ => false,
+        }
+    }
+
+    /// move the menu and send the `msg` to the screen that is open, `now` is the local time and
+    /// `millis` the monotonic clock of the timers
+    pub fn update(&mut self, msg: Msg, now: &DateTime, millis: Millis) -> Option<Effect> {
+        let previous = self.menu_fsm.state;
+        let finished = self.countdown.state == CountdownState::Finished;
+        match AlarmAction::from_msg(msg) {
+            Some(action) if previous == MenuState::Screen(Screen::Alarm) => {
+                self.menu_fsm.back();
+                if core::mem::take(&mut self.countdown_pending) {
+                    self.menu_fsm.show(Screen::Countdown);
+                }
+                return Some(Effect::Alarm(action));
+            }
+            _ => self.menu_fsm.next_state(msg),
+        }
+        let opened = previous != self.menu_fsm.state;
+        match self.menu_fsm.state {
+            MenuState::Screen(Screen::SetClock) if opened => {
+                self.clock_editor = ClockEditor::new(now.clone());
+            }
+            MenuState::Screen(Screen::SetClock) => {
+                let utc = self
+                    .clock_editor
+                    .update(msg)
+                    .and_then(|_| self.timezone.to_utc(&self.clock_editor.datetime))?;
+                self.menu_fsm.back();
+                return Some(Effect::SetTime(utc));
+            }
+            MenuState::Screen(Screen::TimeZone) if opened => {
+                self.timezone_editor = TimeZoneEditor::new(self.timezone);
+            }
+            MenuState::Screen(Screen::TimeZone) => {
+                self.timezone = self.timezone_editor.update(msg)?;
+                self.menu_fsm.back();
+                return Some(Effect::SetTimeZone(self.timezone));
+            }
+            MenuState::Screen(Screen::SetAlarm(index)) if opened => {
+                self.alarm_editor = AlarmEditor::new(index, self.alarms[index]);
+            }
+            MenuState::Screen(Screen::SetAlarm(index)) => {
+                self.alarms[index] = self.alarm_editor.update(msg)?;
+                self.menu_fsm.back();
+                return Some(Effect::SetAlarm(index, self.alarms[index]));
+            }
+            MenuState::Screen(Screen::Countdown) if !opened => {
+                self.countdown.update(msg, millis);
+            }
+            MenuState::Screen(Screen::Stopwatch) if !opened => {
+                self.stopwatch.update(msg, millis);
+            }
+            _ => {}
+        }
+        let closed = previous == MenuState::Screen(Screen::Countdown) && opened;
+        if finished && closed && self.menu_fsm.state == MenuState::Screen(Screen::Alarm) {
+            // NOTE: a ringing alarm shown over the countdown keeps it ringing until it is back
+            self.countdown_pending = true;
+        } else if finished && (closed || self.countdown.state != CountdownState::Finished) {
+            return Some(Effect::CountdownReset);
+        }
+        None
+    }
+
+    /// draw the current state, the screens with input draw its own state and the menus show the
+    /// time after an Enter
+    pub fn draw<D>(
+        &self,
+        target: &mut D,
+        now: &DateTime,
+        millis: Millis,
+        diagnostics: &Diagnostics,
+        msg: Msg,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        match self.menu_fsm.state {
+            MenuState::Screen(Screen::SetClock) => self.clock_editor.draw(target),
+            MenuState::Screen(Screen::TimeZone) => self.timezone_editor.draw(target),
+            MenuState::Screen(Screen::SetAlarm(_)) => self.alarm_editor.draw(target),
+            MenuState::Screen(Screen::Alarm) => draw_alarm(target, now),
+            MenuState::Screen(Screen::Clock) => draw_clock(target, now),
+            MenuState::Screen(Screen::Diagnostics) => diagnostics.draw(target),
+            MenuState::Screen(Screen::Countdown) => self.countdown.draw(target, millis),
+            MenuState::Screen(Screen::Stopwatch) => self.stopwatch.draw(target, millis),
+            state if msg == Msg::Enter => {
+                let mut text: String<32> = String::new();
+                write!(
+                    &mut text,
+                    "{}\n{}",
+                    now.display(DateFormat::Time24),
+                    now.display(DateFormat::DayMonthYear)
+                )
+                .ok();
+                ui::draw_menu(target, state, Some(&text))
+            }
+            state => ui::draw_menu(target, state, None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::canvas::Canvas;
+    use crate::ui::Key;
+
+    #[test]
+    fn test_app_fsm() {
+        let now = DateTime::new(0);
+        let mut app = AppFSM::new(now.clone(), TimeZone::UTC, Default::default());
+        assert!(!app.is_animated());
+        // a countdown of 1 second
+        app.menu_fsm.show(Screen::Countdown);
+        assert!(app.is_animated());
+        for msg in [Msg::Down, Msg::Enter, Msg::Up, Msg::Enter] {
+            assert_eq!(app.update(msg, &now, 0), None);
+        }
+        assert!(app.is_running());
+        // it finishes in other screen
+        app.update(Msg::LongPress(Key::Enter), &now, 0);
+        assert!(!app.is_running());
+        assert!(!app.poll(999));
+        assert!(app.poll(1_000));
+        assert_eq!(app.menu_fsm.state, MenuState::Screen(Screen::Countdown));
+        assert_eq!(
+            app.update(Msg::Enter, &now, 1_000),
+            Some(Effect::CountdownReset)
+        );
+        let mut canvas = Canvas::new();
+        app.draw(
+            &mut canvas,
+            &now,
+            1_000,
+            &Diagnostics::default(),
+            Msg::Enter,
+        )
+        .unwrap();
+        assert!(canvas.rows().flatten().any(|&byte| byte != 0));
+        // closing the screen of a finished countdown stops it too
+        assert!(!app.poll(2_000));
+        for msg in [Msg::Enter, Msg::Enter] {
+            app.update(msg, &now, 2_000);
+        }
+        assert!(app.poll(3_000));
+        assert_eq!(
+            app.update(Msg::LongPress(Key::Enter), &now, 3_000),
+            Some(Effect::CountdownReset)
+        );
+        // the alarm closes its screen
+        app.update(Msg::Alarm, &now, 0);
+        assert_eq!(
+            app.update(Msg::Enter, &now, 0),
+            Some(Effect::Alarm(AlarmAction::Stop))
+        );
+        assert!(app.menu_fsm.state.is_menu());
+    }
+
+    /// a countdown of 1 second that is running in other screen
+    fn start_countdown(app: &mut AppFSM, now: &DateTime) {
+        app.menu_fsm.show(Screen::Countdown);
+        for msg in [Msg::Down, Msg::Enter, Msg::Up, Msg::Enter] {
+            app.update(msg, now, 0);
+        }
+        app.menu_fsm.back();
+    }
+
+    #[test]
+    fn test_countdown_behind_alarm() {
+        let now = DateTime::new(0);
+        let mut app = AppFSM::new(now.clone(), TimeZone::UTC, Default::default());
+        start_countdown(&mut app, &now);
+        app.update(Msg::Alarm, &now, 500);
+        // the countdown doesn't hide the alarm, it is shown when the alarm is stopped
+        assert!(app.poll(1_000));
+        assert_eq!(app.menu_fsm.state, MenuState::Screen(Screen::Alarm));
+        assert_eq!(
+            app.update(Msg::Enter, &now, 1_500),
+            Some(Effect::Alarm(AlarmAction::Stop))
+        );
+        assert_eq!(app.menu_fsm.state, MenuState::Screen(Screen::Countdown));
+        assert_eq!(
+            app.update(Msg::Enter, &now, 2_000),
+            Some(Effect::CountdownReset)
+        );
+        assert_eq!(app.menu_fsm.state, MenuState::Screen(Screen::Countdown));
+    }
+
+    #[test]
+    fn test_alarm_over_countdown() {
+        let now = DateTime::new(0);
+        let mut app = AppFSM::new(now.clone(), TimeZone::UTC, Default::default());
+        start_countdown(&mut app, &now);
+        assert!(app.poll(1_000));
+        // the alarm covers the finished countdown, that keeps ringing until it is back
+        assert_eq!(app.update(Msg::Alarm, &now, 1_500), None);
+        assert_eq!(app.menu_fsm.state, MenuState::Screen(Screen::Alarm));
+        assert_eq!(
+            app.update(Msg::Up, &now, 2_000),
+            Some(Effect::Alarm(AlarmAction::Snooze))
+        );
+        assert_eq!(app.menu_fsm.state, MenuState::Screen(Screen::Countdown));
+        assert_eq!(
+            app.update(Msg::LongPress(Key::Enter), &now, 2_500),
+            Some(Effect::CountdownReset)
+        );
+        assert!(app.menu_fsm.state.is_menu());
+    }
+}
@@ -0,0 +1,264 @@
+/// Button primitives and implementations
+use core::convert::Infallible;
+use embedded_hal::digital::v2::InputPin;
+
+use crate::debounce::{Debounce, Integrator};
+
+pub enum PinState {
+    PinUp,
+    PinDown,
+    Nothing,
+}
+
+type Ticks = u16;
+
+/// Period in milliseconds of the polling of the buttons that the default debounce and
+/// `GestureConfig` are tuned for
+pub const POLL_PERIOD_MS: u32 = 10;
+
+/// the number of polls in `ms` milliseconds
+const fn ticks(ms: u32) -> Ticks {
+    (ms / POLL_PERIOD_MS) as Ticks
+}
+
+/// The gestures that a button can generate
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Gesture {
+    Click,
+    DoubleClick,
+    LongPress,
+    /// generated periodically while the button is held after a `LongPress`
+    Repeat,
+}
+
+/// Timing of the gestures, all the values are in number of calls to `Button::poll_gesture`, the
+/// default is a long press of 600 ms and a repeat of 150 ms with the `POLL_PERIOD_MS`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GestureConfig {
+    /// hold time for a `LongPress`, zero disables it
+    pub long_press: Ticks,
+    /// period of the `Repeat` after a `LongPress`, zero disables it
+    pub repeat: Ticks,
+    /// maximum time between two clicks for a `DoubleClick`, zero disables it. Note that when
+    /// enabled the `Click` is only reported after this time and that it includes the debounce
+    /// of the second press
+    pub double_click: Ticks,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        Self {
+            long_press: ticks(600),
+            repeat: ticks(150),
+            double_click: 0,
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+enum GestureState {
+    Idle,
+    Pressed(Ticks),
+    Held(Ticks),
+    Released(Ticks),
+    SecondPress,
+}
+
+/// A button with a configurable debounce algorithm, by default the `Integrator`
+pub struct Button<P, D = Integrator> {
+    typ: P,
+    debounce: D,
+    config: GestureConfig,
+    gesture: GestureState,
+}
+
+impl<P: InputPin<Error = Infallible>> Button<P> {
+    pub fn new(typ: P) -> Self {
+        Self::with_config(typ, GestureConfig::default())
+    }
+
+    pub fn with_config(typ: P, config: GestureConfig) -> Self {
+        Self::with_debounce(typ, Integrator::default(), config)
+    }
+}
+
+impl<P: InputPin<Error = Infallible>, D: Debounce> Button<P, D> {
+    pub fn with_debounce(typ: P, debounce: D, config: GestureConfig) -> Self {
+        Self {
+            typ,
+            debounce,
+            config,
+            gesture: GestureState::Idle,
+        }
+    }
+
+    /// poll the pin and generate a debounce algorithm:
+    pub fn poll(&mut self) -> PinState {
+        let value = self.typ.is_high().expect("could this fail???");
+        self.debounce.update(value)
+    }
+
+    /// true when the button is released and there is not a gesture in progress, so the polling
+    /// could stop until the next edge of the pin
+    pub fn is_idle(&self) -> bool {
+        matches!(self.gesture, GestureState::Idle) && self.debounce.is_settled()
+    }
+
+    /// poll the pin and recognize the gestures from the debounced edges, this should be called
+    /// periodically because the times of the `GestureConfig` are counted in calls
+    pub fn poll_gesture(&mut self) -> Option<Gesture> {
+        use self::GestureState::*;
+        use self::PinState::*;
+        let config = self.config;
+        let (next, gesture) = match (self.gesture, self.poll()) {
+            (Idle, PinUp) => (Pressed(0), None),
+            (Idle, _) => (Idle, None),
+            (Pressed(_), PinDown) if config.double_click == 0 => (Idle, Some(Gesture::Click)),
+            (Pressed(_), PinDown) => (Released(0), None),
+            (Pressed(ticks), _) if config.long_press != 0 && ticks + 1 >= config.long_press => {
+                (Held(0), Some(Gesture::LongPress))
+            }
+            (Pressed(ticks), _) => (Pressed(ticks.saturating_add(1)), None),
+            (Held(_), PinDown) => (Idle, None),
+            (Held(ticks), _) if config.repeat != 0 && ticks + 1 >= config.repeat => {
+                (Held(0), Some(Gesture::Repeat))
+            }
+            (Held(ticks), _) => (Held(ticks.saturating_add(1)), None),
+            (Released(_), PinUp) => (SecondPress, Some(Gesture::DoubleClick)),
+            (Released(ticks), _) if ticks + 1 >= config.double_click => {
+                (Idle, Some(Gesture::Click))
+            }
+            (Released(ticks), _) => (Released(ticks + 1), None),
+            (SecondPress, PinDown) => (Idle, None),
+            (SecondPress, _) => (SecondPress, None),
+        };
+        self.gesture = next;
+        gesture
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::cell::Cell;
+    use heapless::Vec;
+
+    /// a pin that reads the level from a shared cell
+    struct MockPin<'a>(&'a Cell<bool>);
+
+    impl InputPin for MockPin<'_> {
+        type Error = Infallible;
+
+        fn is_high(&self) -> Result<bool, Self::Error> {
+            Ok(self.0.get())
+        }
+
+        fn is_low(&self) -> Result<bool, Self::Error> {
+            Ok(!self.0.get())
+        }
+    }
+
+    /// keep the pin in `level` for `ticks` polls and collect the gestures
+    fn hold<D: Debounce>(
+        button: &mut Button<MockPin, D>,
+        pin: &Cell<bool>,
+        level: bool,
+        ticks: usize,
+    ) -> Vec<Gesture, 32> {
+        pin.set(level);
+        (0..ticks).filter_map(|_| button.poll_gesture()).collect()
+    }
+
+    const PRESSED: bool = false;
+    const RELEASED: bool = true;
+    const CONFIG: GestureConfig = GestureConfig {
+        long_press: 30,
+        repeat: 5,
+        double_click: 20,
+    };
+
+    #[test]
+    fn test_debounce() {
+        let pin = Cell::new(RELEASED);
+        let mut button = Button::new(MockPin(&pin));
+        // a bounce shorter than the threshold is ignored
+        assert!(hold(&mut button, &pin, PRESSED, 2).is_empty());
+        assert!(hold(&mut button, &pin, RELEASED, 1).is_empty());
+        pin.set(PRESSED);
+        assert!((0..2).all(|_| matches!(button.poll(), PinState::Nothing)));
+        assert!(matches!(button.poll(), PinState::PinUp));
+        pin.set(RELEASED);
+        assert!((0..2).all(|_| matches!(button.poll(), PinState::Nothing)));
+        assert!(matches!(button.poll(), PinState::PinDown));
+    }
+
+    #[test]
+    fn test_click() {
+        let pin = Cell::new(RELEASED);
+        let mut button = Button::new(MockPin(&pin));
+        assert!(button.is_idle());
+        assert!(hold(&mut button, &pin, PRESSED, 20).is_empty());
+        assert!(!button.is_idle());
+        assert_eq!(hold(&mut button, &pin, RELEASED, 20), [Gesture::Click]);
+        assert!(button.is_idle());
+    }
+
+    #[test]
+    fn test_double_click() {
+        let pin = Cell::new(RELEASED);
+        let mut button = Button::with_config(MockPin(&pin), CONFIG);
+        assert!(hold(&mut button, &pin, PRESSED, 20).is_empty());
+        // the click is delayed until the double click time is over
+        assert!(hold(&mut button, &pin, RELEASED, 20).is_empty());
+        assert_eq!(hold(&mut button, &pin, RELEASED, 10), [Gesture::Click]);
+
+        assert!(hold(&mut button, &pin, PRESSED, 20).is_empty());
+        assert!(hold(&mut button, &pin, RELEASED, 5).is_empty());
+        assert_eq!(hold(&mut button, &pin, PRESSED, 20), [Gesture::DoubleClick]);
+        assert!(hold(&mut button, &pin, RELEASED, 40).is_empty());
+    }
+
+    #[test]
+    fn test_long_press_and_repeat() {
+        let pin = Cell::new(RELEASED);
+        let mut button = Button::with_config(MockPin(&pin), CONFIG);
+        // 3 ticks of debounce and 30 of hold
+        assert!(hold(&mut button, &pin, PRESSED, 32).is_empty());
+        assert_eq!(hold(&mut button, &pin, PRESSED, 1), [Gesture::LongPress]);
+        assert_eq!(
+            hold(&mut button, &pin, PRESSED, 10),
+            [Gesture::Repeat, Gesture::Repeat]
+        );
+        // the release after a long press is not a click
+        assert!(!hold(&mut button, &pin, RELEASED, 40).contains(&Gesture::Click));
+        assert!(hold(&mut button, &pin, RELEASED, 40).is_empty());
+    }
+
+    /// the gestures of a press of `ms` milliseconds with the default configuration
+    fn press(ms: u32) -> Vec<Gesture, 32> {
+        let pin = Cell::new(RELEASED);
+        let mut button = Button::new(MockPin(&pin));
+        let mut gestures = hold(&mut button, &pin, PRESSED, (ms / POLL_PERIOD_MS) as usize);
+        gestures.extend(hold(&mut button, &pin, RELEASED, 10));
+        gestures
+    }
+
+    #[test]
+    fn test_press_length() {
+        // a click needs a tap of 30 ms and a long press a hold of more than 600 ms
+        assert!(press(20).is_empty());
+        assert_eq!(press(30), [Gesture::Click]);
+        assert_eq!(press(600), [Gesture::Click]);
+        assert_eq!(press(610), [Gesture::LongPress]);
+    }
+
+    #[test]
+    fn test_custom_debounce() {
+        let pin = Cell::new(RELEASED);
+        let debounce = crate::debounce::Majority::new(3);
+        let mut button = Button::with_debounce(MockPin(&pin), debounce, CONFIG);
+        assert!(hold(&mut button, &pin, PRESSED, 3).is_empty());
+        assert!(hold(&mut button, &pin, RELEASED, 21).is_empty());
+        assert_eq!(hold(&mut button, &pin, RELEASED, 1), [Gesture::Click]);
+    }
+}
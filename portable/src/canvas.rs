@@ -0,0 +1,96 @@
+/// An in memory copy of the screen, the display driver doesn't let read its own buffer
+use core::convert::Infallible;
+
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+
+use crate::ui::{DISPLAY_HEIGHT, DISPLAY_WIDTH};
+
+/// bytes of a row of pixels, the first pixel is the most significant bit
+pub const ROW_BYTES: usize = DISPLAY_WIDTH as usize / 8;
+
+/// A 1 bit per pixel image with the size of the display
+pub struct Canvas {
+    buffer: [u8; ROW_BYTES * DISPLAY_HEIGHT as usize],
+}
+
+impl Canvas {
+    pub const fn new() -> Self {
+        Self {
+            buffer: [0; ROW_BYTES * DISPLAY_HEIGHT as usize],
+        }
+    }
+
+    pub fn is_on(&self, x: usize, y: usize) -> bool {
+        self.buffer[y * ROW_BYTES + x / 8] & (0x80 >> (x % 8)) != 0
+    }
+
+    /// the rows of pixels from the top of the screen
+    pub fn rows(&self) -> impl Iterator<Item = &[u8]> {
+        self.buffer.chunks(ROW_BYTES)
+    }
+}
+
+impl Default for Canvas {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OriginDimensions for Canvas {
+    fn size(&self) -> Size {
+        Size::new(DISPLAY_WIDTH as u32, DISPLAY_HEIGHT as u32)
+    }
+}
+
+impl DrawTarget for Canvas {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            // the pixels out of the screen are ignored like in the display
+            if !(0..DISPLAY_WIDTH).contains(&point.x) || !(0..DISPLAY_HEIGHT).contains(&point.y) {
+                continue;
+            }
+            let index = point.y as usize * ROW_BYTES + point.x as usize / 8;
+            let mask = 0x80 >> (point.x % 8);
+            match color {
+                BinaryColor::On => self.buffer[index] |= mask,
+                BinaryColor::Off => self.buffer[index] &= !mask,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use embedded_graphics::primitives::{PrimitiveStyle, Rectangle};
+
+    #[test]
+    fn test_canvas() {
+        let mut canvas = Canvas::new();
+        Rectangle::new(Point::new(6, 1), Size::new(4, 2))
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+            .draw(&mut canvas)
+            .unwrap();
+        Pixel(Point::new(7, 1), BinaryColor::Off)
+            .draw(&mut canvas)
+            .unwrap();
+        Pixel(Point::new(-1, 200), BinaryColor::On)
+            .draw(&mut canvas)
+            .unwrap();
+        assert!(canvas.is_on(6, 1) && !canvas.is_on(7, 1) && canvas.is_on(9, 2));
+        let rows: [&[u8]; 3] = [&[0; ROW_BYTES], &[0b10, 0xc0], &[0b11, 0xc0]];
+        for (row, expected) in canvas.rows().zip(rows) {
+            assert_eq!(&row[..expected.len()], expected);
+        }
+        assert_eq!(canvas.rows().count(), DISPLAY_HEIGHT as usize);
+        canvas.clear(BinaryColor::Off).unwrap();
+        assert!(canvas.rows().flatten().all(|&byte| byte == 0));
+    }
+}
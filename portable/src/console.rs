@@ -0,0 +1,189 @@
+/// Line based commands of the serial console
+use heapless::String;
+
+use crate::datetime::DateTime;
+use crate::log::Level;
+use crate::ui::Key;
+
+/// the maximum length of a command line without the line end
+pub const LINE_LEN: usize = 48;
+
+/// the text of the `help` command, one command per line
+pub const HELP: &str = "help: this list\r\n\
+time get: the local time\r\n\
+time set YYYY-MM-DDTHH:MM:SS: set the local time\r\n\
+menu up|down|enter: press a button\r\n\
+log error|warn|info|debug: the level of the log messages\r\n\
+screen dump: the screen as 64 rows of 32 hex digits";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Help,
+    /// show the local time
+    TimeGet,
+    /// set the local time
+    TimeSet(DateTime),
+    /// a click of a button
+    Menu(Key),
+    /// send the pixels of the screen
+    ScreenDump,
+    /// the log messages less important than the level are not sent
+    LogLevel(Level),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    UnknownCommand,
+    InvalidDateTime,
+    /// the line doesn't fit in `LINE_LEN`
+    LineTooLong,
+}
+
+impl ParseError {
+    pub fn description(self) -> &'static str {
+        use self::ParseError::*;
+        match self {
+            UnknownCommand => "unknown command, try `help`",
+            InvalidDateTime => "invalid date, the format is YYYY-MM-DDTHH:MM:SS",
+            LineTooLong => "line too long",
+        }
+    }
+}
+
+impl Command {
+    /// parse a line without the line end, the words can be separated by any whitespace
+    pub fn parse(line: &str) -> Result<Self, ParseError> {
+        let mut words = line.split_whitespace();
+        let command = match (words.next(), words.next(), words.next()) {
+            (Some("help"), None, None) => Command::Help,
+            (Some("time"), Some("get"), None) => Command::TimeGet,
+            (Some("time"), Some("set"), Some(text)) => {
+                let datetime = DateTime::parse_iso8601(text).ok_or(ParseError::InvalidDateTime)?;
+                Command::TimeSet(datetime)
+            }
+            (Some("menu"), Some("up"), None) => Command::Menu(Key::Up),
+            (Some("menu"), Some("down"), None) => Command::Menu(Key::Down),
+            (Some("menu"), Some("enter"), None) => Command::Menu(Key::Enter),
+            (Some("screen"), Some("dump"), None) => Command::ScreenDump,
+            (Some("log"), Some(name), None) => {
+                Command::LogLevel(Level::from_name(name).ok_or(ParseError::UnknownCommand)?)
+            }
+            _ => return Err(ParseError::UnknownCommand),
+        };
+        match words.next() {
+            Some(_) => Err(ParseError::UnknownCommand),
+            None => Ok(command),
+        }
+    }
+}
+
+/// Collect the received bytes in lines and parse them, the lines end with `\r`, `\n` or both and
+/// the backspace deletes the last character
+#[derive(Debug, Default)]
+pub struct Console {
+    line: String<LINE_LEN>,
+    /// the current line didn't fit, it is ignored until the next line end
+    overflow: bool,
+}
+
+impl Console {
+    pub const fn new() -> Self {
+        Self {
+            line: String::new(),
+            overflow: false,
+        }
+    }
+
+    /// forget the current line, e.g. after a receive error
+    pub fn clear(&mut self) {
+        self.line.clear();
+        self.overflow = false;
+    }
+
+    /// add a received byte, returns the command when a line is complete. The empty lines are
+    /// ignored
+    pub fn push(&mut self, byte: u8) -> Option<Result<Command, ParseError>> {
+        match byte {
+            b'\r' | b'\n' => {
+                let result = match (self.overflow, self.line.trim()) {
+                    (true, _) => Some(Err(ParseError::LineTooLong)),
+                    (false, "") => None,
+                    (false, line) => Some(Command::parse(line)),
+                };
+                self.clear();
+                result
+            }
+            // backspace and delete
+            0x08 | 0x7f => {
+                self.line.pop();
+                None
+            }
+            // only the printable ASCII is saved so the line is always valid UTF-8, a tab separates
+            // the words like a space
+            b'\t' | b' '..=b'~' => {
+                let c = if byte == b'\t' { ' ' } else { char::from(byte) };
+                self.overflow |= self.line.push(c).is_err();
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(Command::parse("help"), Ok(Command::Help));
+        assert_eq!(Command::parse(" time  get "), Ok(Command::TimeGet));
+        assert_eq!(
+            Command::parse("time set 2026-10-18T12:00:00"),
+            Ok(Command::TimeSet(DateTime::new(1792324800)))
+        );
+        assert_eq!(
+            Command::parse("time set 2026-10-32T12:00:00"),
+            Err(ParseError::InvalidDateTime)
+        );
+        assert_eq!(Command::parse("menu down"), Ok(Command::Menu(Key::Down)));
+        assert_eq!(Command::parse("screen dump"), Ok(Command::ScreenDump));
+        assert_eq!(
+            Command::parse("log debug"),
+            Ok(Command::LogLevel(Level::Debug))
+        );
+        for unknown in [
+            "time",
+            "menu left",
+            "help me",
+            "screen dump now",
+            "TIME GET",
+            "log trace",
+        ] {
+            assert_eq!(Command::parse(unknown), Err(ParseError::UnknownCommand));
+        }
+    }
+
+    #[test]
+    fn test_console() {
+        let mut console = Console::new();
+        let mut push = |text: &[u8]| text.iter().filter_map(|&byte| console.push(byte)).last();
+        assert_eq!(push(b"menu u"), None);
+        assert_eq!(push(b"p\r\n"), Some(Ok(Command::Menu(Key::Up))));
+        // the `\n` of a `\r\n` is an empty line
+        assert_eq!(push(b"\n\r \r"), None);
+        assert_eq!(push(b"helq\x08p\n"), Some(Ok(Command::Help)));
+        assert_eq!(push(&[b'x'; LINE_LEN + 1]), None);
+        assert_eq!(push(b"\r"), Some(Err(ParseError::LineTooLong)));
+        assert_eq!(push(b"screen\tdump\r"), Some(Ok(Command::ScreenDump)));
+        assert_eq!(push(b"screen dump\r"), Some(Ok(Command::ScreenDump)));
+        // the bytes before the `clear` are forgotten
+        let mut console = Console::new();
+        b"time ge"
+            .iter()
+            .for_each(|&byte| assert_eq!(console.push(byte), None));
+        console.clear();
+        let result = b"help\r".iter().find_map(|&byte| console.push(byte));
+        assert_eq!(result, Some(Ok(Command::Help)));
+    }
+}
@@ -0,0 +1,584 @@
+use core::fmt::{self, Write};
+
+use heapless::String;
+
+static DAYS_IN_MONTH: [u32; 12] = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+static DAYS_IN_MONTH_LEAP_YEAR: [u32; 12] = [31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+fn days_in_month(is_leap_year: bool) -> &'static [u32; 12] {
+    if is_leap_year {
+        &DAYS_IN_MONTH_LEAP_YEAR
+    } else {
+        &DAYS_IN_MONTH
+    }
+}
+static MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+/// `value + delta` in the range `min..=max`, wrapping in both directions
+pub(crate) fn wrap(value: i32, delta: i32, min: i32, max: i32) -> i32 {
+    min + (value - min + delta).rem_euclid(max - min + 1)
+}
+fn is_leap(year: u16) -> bool {
+    if !year.is_multiple_of(4) {
+        false
+    } else if !year.is_multiple_of(100) {
+        true
+    } else {
+        year.is_multiple_of(400)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DayOfWeek {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+impl DayOfWeek {
+    pub fn from_days_since_epoch(days: u32) -> DayOfWeek {
+        use self::DayOfWeek::*;
+        match days % 7 {
+            4 => Monday,
+            5 => Tuesday,
+            6 => Wednesday,
+            0 => Thursday,
+            1 => Friday,
+            2 => Saturday,
+            3 => Sunday,
+            _ => unreachable!(),
+        }
+    }
+    pub fn next(self) -> Self {
+        use self::DayOfWeek::*;
+        match self {
+            Monday => Tuesday,
+            Tuesday => Wednesday,
+            Wednesday => Thursday,
+            Thursday => Friday,
+            Friday => Saturday,
+            Saturday => Sunday,
+            Sunday => Monday,
+        }
+    }
+    /// the full english name, e.g. `Monday`
+    pub fn name(self) -> &'static str {
+        use self::DayOfWeek::*;
+        match self {
+            Monday => "Monday",
+            Tuesday => "Tuesday",
+            Wednesday => "Wednesday",
+            Thursday => "Thursday",
+            Friday => "Friday",
+            Saturday => "Saturday",
+            Sunday => "Sunday",
+        }
+    }
+    /// the first three letters of the name, e.g. `Mon`
+    pub fn short_name(self) -> &'static str {
+        &self.name()[..3]
+    }
+}
+impl ::core::fmt::Display for DayOfWeek {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> Result<(), ::core::fmt::Error> {
+        f.write_str(self.name())
+    }
+}
+
+/// The formats in which a `DateTime` can be written, the examples are for `2018-10-20 16:21:41`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DateFormat {
+    /// `2018-10-20T16:21:41`
+    Iso8601,
+    /// `2018-10-20`
+    IsoDate,
+    /// `20/10/2018`
+    DayMonthYear,
+    /// `16:21:41`
+    Time24,
+    /// `04:21:41 PM`
+    Time12,
+    /// `16:21`
+    HourMinute,
+    /// `Sat 20 Oct`
+    ShortDate,
+    /// `Saturday 20 October 2018`
+    LongDate,
+}
+
+/// A `DateTime` with a format, see `DateTime::display`
+pub struct Formatted<'a> {
+    datetime: &'a DateTime,
+    format: DateFormat,
+}
+
+impl ::core::fmt::Display for Formatted<'_> {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> Result<(), ::core::fmt::Error> {
+        use self::DateFormat::*;
+        let dt = self.datetime;
+        match self.format {
+            Iso8601 => write!(
+                f,
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+                dt.year, dt.month, dt.day, dt.hour, dt.min, dt.sec
+            ),
+            IsoDate => write!(f, "{:04}-{:02}-{:02}", dt.year, dt.month, dt.day),
+            DayMonthYear => write!(f, "{:02}/{:02}/{:04}", dt.day, dt.month, dt.year),
+            Time24 => write!(f, "{:02}:{:02}:{:02}", dt.hour, dt.min, dt.sec),
+            Time12 => {
+                let hour = match dt.hour % 12 {
+                    0 => 12,
+                    hour => hour,
+                };
+                let suffix = if dt.hour < 12 { "AM" } else { "PM" };
+                write!(f, "{:02}:{:02}:{:02} {}", hour, dt.min, dt.sec, suffix)
+            }
+            HourMinute => write!(f, "{:02}:{:02}", dt.hour, dt.min),
+            ShortDate => write!(
+                f,
+                "{} {} {}",
+                dt.day_of_week.short_name(),
+                dt.day,
+                dt.month_short_name()
+            ),
+            LongDate => write!(
+                f,
+                "{} {} {} {}",
+                dt.day_of_week,
+                dt.day,
+                dt.month_name(),
+                dt.year
+            ),
+        }
+    }
+}
+
+/// The fields of a `DateTime` that can be changed with `DateTime::roll`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DateField {
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub min: u8,
+    pub sec: u8,
+    pub day_of_week: DayOfWeek,
+}
+impl DateTime {
+    /// the first year of the epoch
+    pub const MIN_YEAR: u16 = 1970;
+    /// the last complete year that fits in the `u32` epoch seconds
+    pub const MAX_YEAR: u16 = 2105;
+
+    pub fn new(epoch: u32) -> DateTime {
+        let mut days = epoch / 86400;
+        let time = epoch % 86400;
+        let day_of_week = DayOfWeek::from_days_since_epoch(days);
+        let mut year = 1970;
+        let mut is_leap_year;
+
+        loop {
+            is_leap_year = is_leap(year);
+            if is_leap_year && days >= 366 {
+                year += 1;
+                days -= 366
+            } else if !is_leap_year && days >= 365 {
+                year += 1;
+                days -= 365;
+            } else {
+                break;
+            }
+        }
+        let mut month = 1;
+        for &nb in days_in_month(is_leap_year).iter() {
+            if days < nb {
+                break;
+            }
+            days -= nb;
+            month += 1;
+        }
+        DateTime {
+            year,
+            month,
+            day: (days + 1) as u8,
+            hour: (time / 60 / 60) as u8,
+            min: (time / 60 % 60) as u8,
+            sec: (time % 60) as u8,
+            day_of_week,
+        }
+    }
+    /// number of days of the current month
+    pub fn days_in_month(&self) -> u8 {
+        let month = usize::from(self.month.clamp(1, 12));
+        days_in_month(is_leap(self.year))[month - 1] as u8
+    }
+
+    /// the full english name of the month, e.g. `October`
+    pub fn month_name(&self) -> &'static str {
+        MONTH_NAMES[usize::from(self.month.clamp(1, 12)) - 1]
+    }
+    /// the first three letters of the month name, e.g. `Oct`
+    pub fn month_short_name(&self) -> &'static str {
+        &self.month_name()[..3]
+    }
+
+    /// a value that prints the `DateTime` with `format`, useful with `write!`
+    pub fn display(&self, format: DateFormat) -> Formatted<'_> {
+        Formatted {
+            datetime: self,
+            format,
+        }
+    }
+
+    /// write the `DateTime` with `format` in a new string, fails if it doesn't fit in `N` bytes
+    pub fn format<const N: usize>(&self, format: DateFormat) -> Result<String<N>, fmt::Error> {
+        let mut text = String::new();
+        write!(&mut text, "{}", self.display(format))?;
+        Ok(text)
+    }
+
+    /// parse a `DateFormat::Iso8601` text like `2018-10-20T16:21:41`, a space can also separate
+    /// the date and the time. Returns `None` if the text or the date are not valid
+    pub fn parse_iso8601(text: &str) -> Option<DateTime> {
+        let bytes = text.as_bytes();
+        let separators = [(4, b'-'), (7, b'-'), (13, b':'), (16, b':')];
+        if bytes.len() != 19
+            || !matches!(bytes[10], b'T' | b't' | b' ')
+            || separators.iter().any(|&(index, sep)| bytes[index] != sep)
+        {
+            return None;
+        }
+        let number = |start: usize, len: usize| {
+            let digits = &bytes[start..start + len];
+            digits.iter().all(u8::is_ascii_digit).then(|| {
+                digits
+                    .iter()
+                    .fold(0u16, |acc, digit| acc * 10 + u16::from(digit - b'0'))
+            })
+        };
+        let datetime = DateTime {
+            year: number(0, 4)?,
+            month: number(5, 2)? as u8,
+            day: number(8, 2)? as u8,
+            hour: number(11, 2)? as u8,
+            min: number(14, 2)? as u8,
+            sec: number(17, 2)? as u8,
+            day_of_week: DayOfWeek::Monday,
+        };
+        if !(1..=12).contains(&datetime.month)
+            || datetime.day > datetime.days_in_month()
+            || datetime.hour > 23
+            || datetime.min > 59
+            || datetime.sec > 59
+        {
+            return None;
+        }
+        // the epoch seconds fill the right `day_of_week`
+        datetime.to_epoch().map(DateTime::new)
+    }
+
+    //-------------------------------------------------------------------------
+    //                        arithmetic
+    //-------------------------------------------------------------------------
+    // NOTE: the operations go through the epoch seconds so the overflow between the
+    // fields and the `day_of_week` are always right, they return `None` out of the epoch range
+
+    /// add `seconds`(or subtract if it is negative)
+    pub fn add_seconds(&self, seconds: i64) -> Option<DateTime> {
+        let epoch = i64::from(self.to_epoch()?).checked_add(seconds)?;
+        u32::try_from(epoch).ok().map(DateTime::new)
+    }
+
+    pub fn add_minutes(&self, minutes: i64) -> Option<DateTime> {
+        self.add_seconds(minutes.checked_mul(60)?)
+    }
+
+    pub fn add_hours(&self, hours: i64) -> Option<DateTime> {
+        self.add_seconds(hours.checked_mul(3600)?)
+    }
+
+    pub fn add_days(&self, days: i64) -> Option<DateTime> {
+        self.add_seconds(days.checked_mul(86400)?)
+    }
+
+    /// add calendar months, the day is clamped to the length of the new month(e.g. 31 of
+    /// january plus one month is 28 or 29 of february)
+    pub fn add_months(&self, months: i32) -> Option<DateTime> {
+        let months = (i32::from(self.year) * 12 + i32::from(self.month) - 1).checked_add(months)?;
+        let mut datetime = DateTime {
+            year: u16::try_from(months.div_euclid(12)).ok()?,
+            month: (months.rem_euclid(12) + 1) as u8,
+            ..self.clone()
+        };
+        datetime.day = datetime.day.min(datetime.days_in_month());
+        datetime.to_epoch().map(DateTime::new)
+    }
+
+    /// the seconds from `earlier` to `self`, negative if `earlier` is after `self`
+    pub fn seconds_since(&self, earlier: &DateTime) -> Option<i64> {
+        Some(i64::from(self.to_epoch()?) - i64::from(earlier.to_epoch()?))
+    }
+
+    /// change only `field` by `delta` wrapping in its range without touching the other
+    /// fields(e.g. the day 31 goes to 1 and the month stays the same)
+    pub fn roll(&self, field: DateField, delta: i32) -> DateTime {
+        use self::DateField::*;
+        let mut dt = self.clone();
+        match field {
+            Year => {
+                let (min, max) = (i32::from(Self::MIN_YEAR), i32::from(Self::MAX_YEAR));
+                dt.year = wrap(i32::from(dt.year), delta, min, max) as u16
+            }
+            Month => dt.month = wrap(i32::from(dt.month), delta, 1, 12) as u8,
+            Day => dt.day = wrap(i32::from(dt.day), delta, 1, i32::from(dt.days_in_month())) as u8,
+            Hour => dt.hour = wrap(i32::from(dt.hour), delta, 0, 23) as u8,
+            Minute => dt.min = wrap(i32::from(dt.min), delta, 0, 59) as u8,
+            Second => dt.sec = wrap(i32::from(dt.sec), delta, 0, 59) as u8,
+        }
+        // a shorter month or a non leap year could leave the day out of range
+        dt.day = dt.day.min(dt.days_in_month());
+        match dt.to_epoch() {
+            Some(epoch) => DateTime::new(epoch),
+            None => dt,
+        }
+    }
+
+    pub fn to_epoch(&self) -> Option<u32> {
+        if self.year < 1970 || self.month == 0 || self.month > 12 || self.day == 0 {
+            return None;
+        }
+        let mut days = u32::from(self.day) - 1;
+        for y in 1970..self.year {
+            if is_leap(y) {
+                days = days.checked_add(366)?;
+            } else {
+                days = days.checked_add(365)?;
+            }
+        }
+
+        for &m in days_in_month(is_leap(self.year))
+            .iter()
+            .take(self.month as usize - 1)
+        {
+            days = days.checked_add(m)?;
+        }
+
+        let time = u32::from(self.hour) * 3600 + u32::from(self.min) * 60 + u32::from(self.sec);
+        let epoch = days.checked_mul(86400)?.checked_add(time)?;
+        Some(epoch)
+    }
+}
+impl ::core::fmt::Display for DateTime {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter) -> Result<(), ::core::fmt::Error> {
+        write!(f, "{}", self.display(DateFormat::Iso8601))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const EPOCH: DateTime = DateTime {
+        year: 1970,
+        month: 1,
+        day: 1,
+        hour: 0,
+        min: 0,
+        sec: 0,
+        day_of_week: DayOfWeek::Thursday,
+    };
+    const END_OF_TIME: DateTime = DateTime {
+        year: 2106,
+        month: 2,
+        day: 7,
+        hour: 6,
+        min: 28,
+        sec: 15,
+        day_of_week: DayOfWeek::Sunday,
+    };
+
+    #[test]
+    fn test_epoch() {
+        assert_eq!(DateTime::new(0), EPOCH);
+        assert_eq!(DateTime::new(u32::MAX), END_OF_TIME);
+        assert_eq!(
+            DateTime::new(1540052501),
+            DateTime {
+                year: 2018,
+                month: 10,
+                day: 20,
+                hour: 16,
+                min: 21,
+                sec: 41,
+                day_of_week: DayOfWeek::Saturday,
+            }
+        );
+        assert_eq!(EPOCH.to_epoch(), Some(0));
+        assert_eq!(END_OF_TIME.to_epoch(), Some(u32::MAX));
+        assert_eq!(
+            DateTime {
+                sec: 16,
+                ..END_OF_TIME
+            }
+            .to_epoch(),
+            None
+        );
+        assert_eq!(
+            DateTime {
+                year: 1969,
+                month: 12,
+                day: 31,
+                hour: 23,
+                min: 59,
+                sec: 59,
+                day_of_week: DayOfWeek::Wednesday,
+            }
+            .to_epoch(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        // 2018-10-20 16:21:41
+        let datetime = DateTime::new(1540052501);
+        let format = |format| datetime.format::<32>(format).unwrap();
+        assert_eq!(format(DateFormat::Iso8601), "2018-10-20T16:21:41");
+        assert_eq!(format(DateFormat::IsoDate), "2018-10-20");
+        assert_eq!(format(DateFormat::DayMonthYear), "20/10/2018");
+        assert_eq!(format(DateFormat::Time24), "16:21:41");
+        assert_eq!(format(DateFormat::Time12), "04:21:41 PM");
+        assert_eq!(format(DateFormat::HourMinute), "16:21");
+        assert_eq!(format(DateFormat::ShortDate), "Sat 20 Oct");
+        assert_eq!(format(DateFormat::LongDate), "Saturday 20 October 2018");
+        assert_eq!(
+            EPOCH.format::<16>(DateFormat::Time12).unwrap(),
+            "12:00:00 AM"
+        );
+        // `N` is too small for the text
+        assert!(datetime.format::<8>(DateFormat::Iso8601).is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        let datetime = DateTime::new(1540052501);
+        assert_eq!(
+            DateTime::parse_iso8601("2018-10-20T16:21:41"),
+            Some(datetime.clone())
+        );
+        assert_eq!(
+            DateTime::parse_iso8601("2018-10-20 16:21:41"),
+            Some(datetime)
+        );
+        assert_eq!(
+            DateTime::parse_iso8601("2024-02-29T00:00:00").map(|dt| dt.day_of_week),
+            Some(DayOfWeek::Thursday)
+        );
+        for invalid in [
+            "",
+            "2018-10-20",
+            "2018-10-20T16:21",
+            "2018/10/20T16:21:41",
+            "2018-10-2xT16:21:41",
+            "2018-+1-20T16:21:41",
+            "2023-02-29T00:00:00",
+            "2018-13-20T16:21:41",
+            "2018-10-00T16:21:41",
+            "2018-10-20T24:00:00",
+            "1969-12-31T23:59:59",
+            "2106-02-07T06:28:16",
+        ] {
+            assert_eq!(DateTime::parse_iso8601(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        // 2024-02-28 23:59:30, a leap year
+        let datetime = DateTime::new(1709164770);
+        assert_eq!(
+            datetime.add_seconds(45),
+            Some(DateTime {
+                year: 2024,
+                month: 2,
+                day: 29,
+                hour: 0,
+                min: 0,
+                sec: 15,
+                day_of_week: DayOfWeek::Thursday,
+            })
+        );
+        let new_year = datetime.add_days(307).unwrap().add_minutes(1).unwrap();
+        assert_eq!(
+            (
+                new_year.year,
+                new_year.month,
+                new_year.day,
+                new_year.hour,
+                new_year.min
+            ),
+            (2025, 1, 1, 0, 0)
+        );
+        assert_eq!(new_year.day_of_week, DayOfWeek::Wednesday);
+        assert_eq!(new_year.add_hours(-1).unwrap().year, 2024);
+        assert_eq!(new_year.seconds_since(&datetime), Some(307 * 86400 + 60));
+        assert_eq!(datetime.seconds_since(&new_year), Some(-(307 * 86400 + 60)));
+        // the day is clamped to the end of the month
+        let january = DateTime::new(1706659200); // 2024-01-31
+        let february = january.add_months(1).unwrap();
+        assert_eq!((february.month, february.day), (2, 29));
+        assert_eq!(february.day_of_week, DayOfWeek::Thursday);
+        let previous = january.add_months(-13).unwrap();
+        assert_eq!(
+            (previous.year, previous.month, previous.day),
+            (2022, 12, 31)
+        );
+        // out of the epoch range
+        assert_eq!(EPOCH.add_seconds(-1), None);
+        assert_eq!(END_OF_TIME.add_seconds(1), None);
+        assert_eq!(END_OF_TIME.add_months(1), None);
+    }
+
+    #[test]
+    fn test_roll() {
+        // 2023-01-31 23:59:59
+        let datetime = DateTime::new(1675209599);
+        let rolled = datetime.roll(DateField::Day, 1);
+        assert_eq!((rolled.month, rolled.day), (1, 1));
+        assert_eq!(rolled.day_of_week, DayOfWeek::Sunday);
+        let rolled = datetime.roll(DateField::Second, 1);
+        assert_eq!((rolled.min, rolled.sec), (59, 0));
+        assert_eq!(datetime.roll(DateField::Hour, 1).hour, 0);
+        assert_eq!(datetime.roll(DateField::Minute, -60).min, 59);
+        // february of 2023 only has 28 days
+        let rolled = datetime.roll(DateField::Month, 1);
+        assert_eq!((rolled.month, rolled.day), (2, 28));
+        assert_eq!(rolled.day_of_week, DayOfWeek::Tuesday);
+        assert_eq!(datetime.roll(DateField::Month, -1).month, 12);
+        assert_eq!(EPOCH.roll(DateField::Year, -1).year, DateTime::MAX_YEAR);
+    }
+}
@@ -0,0 +1,256 @@
+/// Debounce algorithms for the buttons
+use crate::buttons::PinState;
+
+/// A debounce algorithm, it receives the raw level of the pin in every poll and returns the
+/// debounced edges(`PinUp` when the pin goes low, `PinDown` when goes high again)
+pub trait Debounce {
+    fn update(&mut self, is_high: bool) -> PinState;
+
+    /// true when there is not a pending change of level, so the pin could stop being polled
+    fn is_settled(&self) -> bool;
+}
+
+type Counter = u8;
+
+#[derive(Copy, Clone)]
+enum IntegratorState {
+    High(Counter),
+    Low(Counter),
+}
+
+/// Counts the consecutive samples with the opposite level and any bounce restarts the count, good
+/// for clean tactile switches
+#[derive(Copy, Clone)]
+pub struct Integrator {
+    threshold: Counter,
+    state: IntegratorState,
+}
+
+impl Integrator {
+    /// 30 ms with the `POLL_PERIOD_MS` of the buttons
+    pub const DEFAULT_THRESHOLD: Counter = 3;
+
+    /// `threshold` is at least 1 sample
+    pub fn new(threshold: Counter) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            state: IntegratorState::High(0),
+        }
+    }
+}
+
+impl Default for Integrator {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_THRESHOLD)
+    }
+}
+
+impl Debounce for Integrator {
+    fn update(&mut self, is_high: bool) -> PinState {
+        use self::IntegratorState::*;
+        match (&mut self.state, is_high) {
+            (High(counter), true) => *counter = 0,
+            (High(counter), false) => *counter = counter.saturating_add(1),
+            (Low(counter), true) => *counter = counter.saturating_add(1),
+            (Low(counter), false) => *counter = 0,
+        }
+        match self.state {
+            High(counter) if counter >= self.threshold => {
+                self.state = Low(0);
+                PinState::PinUp
+            }
+            Low(counter) if counter >= self.threshold => {
+                self.state = High(0);
+                PinState::PinDown
+            }
+            _ => PinState::Nothing,
+        }
+    }
+
+    fn is_settled(&self) -> bool {
+        matches!(
+            self.state,
+            IntegratorState::High(0) | IntegratorState::Low(0)
+        )
+    }
+}
+
+/// Keeps the last `width` samples in a shift register and changes when the majority of them
+/// have the opposite level, so isolated glitches of a noisy mechanical switch don't restart the
+/// count like in the `Integrator`
+#[derive(Copy, Clone)]
+pub struct Majority {
+    width: u32,
+    history: u32,
+    low: bool,
+}
+
+impl Majority {
+    /// `width` is clamped to 1..=32 samples
+    pub fn new(width: u8) -> Self {
+        let width = u32::from(width.clamp(1, 32));
+        Self {
+            width,
+            history: u32::MAX >> (32 - width),
+            low: false,
+        }
+    }
+}
+
+impl Debounce for Majority {
+    fn update(&mut self, is_high: bool) -> PinState {
+        let mask = u32::MAX >> (32 - self.width);
+        self.history = ((self.history << 1) | u32::from(is_high)) & mask;
+        let highs = self.history.count_ones();
+        let lows = self.width - highs;
+        match self.low {
+            false if lows > self.width / 2 => {
+                self.low = true;
+                PinState::PinUp
+            }
+            true if highs > self.width / 2 => {
+                self.low = false;
+                PinState::PinDown
+            }
+            _ => PinState::Nothing,
+        }
+    }
+
+    fn is_settled(&self) -> bool {
+        let mask = u32::MAX >> (32 - self.width);
+        if self.low {
+            self.history == 0
+        } else {
+            self.history == mask
+        }
+    }
+}
+
+/// Changes when the new level is stable for `stable_ms` milliseconds of the `clock`, so it
+/// does not depend on the period of the polling
+#[derive(Copy, Clone)]
+pub struct TimeBased {
+    clock: fn() -> u32,
+    stable_ms: u32,
+    low: bool,
+    changed_at: Option<u32>,
+}
+
+impl TimeBased {
+    /// `clock` returns the current time in milliseconds(e.g from the RTIC monotonic)
+    pub fn new(clock: fn() -> u32, stable_ms: u32) -> Self {
+        Self {
+            clock,
+            stable_ms,
+            low: false,
+            changed_at: None,
+        }
+    }
+}
+
+impl Debounce for TimeBased {
+    fn update(&mut self, is_high: bool) -> PinState {
+        if is_high != self.low {
+            self.changed_at = None;
+            return PinState::Nothing;
+        }
+        let now = (self.clock)();
+        match self.changed_at {
+            Some(since) if now.wrapping_sub(since) >= self.stable_ms => {
+                self.changed_at = None;
+                self.low = !self.low;
+                if self.low {
+                    PinState::PinUp
+                } else {
+                    PinState::PinDown
+                }
+            }
+            Some(_) => PinState::Nothing,
+            None => {
+                self.changed_at = Some(now);
+                PinState::Nothing
+            }
+        }
+    }
+
+    fn is_settled(&self) -> bool {
+        self.changed_at.is_none()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    fn edges<D: Debounce>(debounce: &mut D, samples: &[bool]) -> (usize, usize) {
+        samples
+            .iter()
+            .fold((0, 0), |(up, down), &level| match debounce.update(level) {
+                PinState::PinUp => (up + 1, down),
+                PinState::PinDown => (up, down + 1),
+                PinState::Nothing => (up, down),
+            })
+    }
+
+    const NOISY_PRESS: [bool; 12] = [
+        false, true, false, false, true, false, false, false, false, false, false, false,
+    ];
+
+    #[test]
+    fn test_integrator() {
+        let mut integrator = Integrator::new(4);
+        // any bounce restart the count
+        assert_eq!(edges(&mut integrator, &NOISY_PRESS[..8]), (0, 0));
+        assert_eq!(edges(&mut integrator, &NOISY_PRESS[8..]), (1, 0));
+        assert_eq!(edges(&mut integrator, &[true; 3]), (0, 0));
+        assert_eq!(edges(&mut integrator, &[true]), (0, 1));
+    }
+
+    #[test]
+    fn test_integrator_min_threshold() {
+        let mut integrator = Integrator::new(0);
+        // a stable level doesn't report edges
+        assert_eq!(edges(&mut integrator, &[true; 4]), (0, 0));
+        assert_eq!(edges(&mut integrator, &[false; 4]), (1, 0));
+        assert_eq!(edges(&mut integrator, &[true; 4]), (0, 1));
+    }
+
+    #[test]
+    fn test_majority() {
+        let mut majority = Majority::new(5);
+        // the bounces don't restart the count
+        assert_eq!(edges(&mut majority, &NOISY_PRESS[..3]), (0, 0));
+        assert_eq!(edges(&mut majority, &NOISY_PRESS[3..4]), (1, 0));
+        assert_eq!(edges(&mut majority, &NOISY_PRESS[4..]), (0, 0));
+        assert_eq!(edges(&mut majority, &[true, true, false]), (0, 0));
+        assert_eq!(edges(&mut majority, &[true]), (0, 1));
+        // a single glitch is ignored
+        assert_eq!(edges(&mut majority, &[false, true, true]), (0, 0));
+        assert!(!majority.is_settled());
+        assert_eq!(edges(&mut majority, &[true, true, true]), (0, 0));
+        assert!(majority.is_settled());
+    }
+
+    static NOW: AtomicU32 = AtomicU32::new(0);
+
+    fn now() -> u32 {
+        NOW.load(Ordering::Relaxed)
+    }
+
+    #[test]
+    fn test_time_based() {
+        let mut time = TimeBased::new(now, 20);
+        let mut sample = |ms: u32, level: bool| {
+            NOW.store(ms, Ordering::Relaxed);
+            time.update(level)
+        };
+        assert!(matches!(sample(0, false), PinState::Nothing));
+        assert!(matches!(sample(5, true), PinState::Nothing));
+        assert!(matches!(sample(10, false), PinState::Nothing));
+        assert!(matches!(sample(29, false), PinState::Nothing));
+        assert!(matches!(sample(30, false), PinState::PinUp));
+        assert!(matches!(sample(100, true), PinState::Nothing));
+        assert!(matches!(sample(200, true), PinState::PinDown));
+    }
+}
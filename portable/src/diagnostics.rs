@@ -0,0 +1,218 @@
+/// The last panic, the error counters and the screens that show them
+use core::fmt;
+
+use embedded_graphics::{
+    mono_font::{ascii::FONT_6X9, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    text::{Baseline, Text},
+};
+use heapless::String;
+
+use crate::error::ErrorCounters;
+
+/// characters of a `FONT_6X9` row in the display
+const COLS: usize = 21;
+/// height in pixels of a `FONT_6X9` row
+const ROW_HEIGHT: i32 = 9;
+/// the rows that fit in the display
+const ROWS: usize = 7;
+/// characters of the file name saved in a `PanicRecord`
+const FILE_TAG_LEN: usize = 4;
+/// the high byte of the first register of a valid `PanicRecord`
+const PANIC_MAGIC: u16 = 0xa5;
+
+/// A compact record of the last panic, it fits in 4 backup registers so it survives the reset
+/// and can be shown in the next boot
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PanicRecord {
+    /// the first characters of the file name without the extension, padded with NUL
+    file: [u8; FILE_TAG_LEN],
+    pub line: u16,
+    /// the panics since the registers were cleared, it stops at 255
+    pub count: u8,
+}
+
+impl PanicRecord {
+    /// the record of a panic in `file` and `line`, it counts the `previous` ones
+    pub fn new(file: &str, line: u32, previous: Option<PanicRecord>) -> Self {
+        let name = file.rsplit(['/', '\\']).next().unwrap_or(file);
+        let stem = name.split('.').next().unwrap_or(name);
+        let mut tag = [0; FILE_TAG_LEN];
+        for (byte, c) in tag.iter_mut().zip(stem.bytes()) {
+            *byte = if c.is_ascii_graphic() { c } else { b'?' };
+        }
+        Self {
+            file: tag,
+            line: line.min(u16::MAX as u32) as u16,
+            count: previous.map_or(1, |record| record.count.saturating_add(1)),
+        }
+    }
+
+    /// the start of the file name where the panic was
+    pub fn file(&self) -> &str {
+        let len = self.file.iter().position(|&byte| byte == 0);
+        core::str::from_utf8(&self.file[..len.unwrap_or(FILE_TAG_LEN)]).unwrap_or("?")
+    }
+
+    /// the values of the 4 backup registers
+    pub fn to_bits(&self) -> [u16; 4] {
+        let [f0, f1, f2, f3] = self.file.map(u16::from);
+        [
+            PANIC_MAGIC << 8 | self.count as u16,
+            self.line,
+            f0 << 8 | f1,
+            f2 << 8 | f3,
+        ]
+    }
+
+    /// the record saved in the backup registers, `None` if there was no panic since they were
+    /// cleared
+    pub fn from_bits(bits: [u16; 4]) -> Option<Self> {
+        let count = (bits[0] & 0xff) as u8;
+        if bits[0] >> 8 != PANIC_MAGIC || count == 0 {
+            return None;
+        }
+        let [_, _, [f0, f1], [f2, f3]] = bits.map(u16::to_be_bytes);
+        let file = [f0, f1, f2, f3];
+        // a corrupted name is not shown
+        if !file
+            .iter()
+            .all(|&byte| byte == 0 || byte.is_ascii_graphic())
+        {
+            return None;
+        }
+        Some(Self {
+            file,
+            line: bits[1],
+            count,
+        })
+    }
+}
+
+impl fmt::Display for PanicRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file(), self.line)
+    }
+}
+
+/// The state of the firmware shown in the diagnostics screen
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Diagnostics {
+    /// the last panic before the boot
+    pub panic: Option<PanicRecord>,
+    pub errors: ErrorCounters,
+}
+
+impl Diagnostics {
+    pub fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let mut text: String<{ (ROWS - 1) * (COLS + 1) }> = String::new();
+        match self.panic {
+            Some(record) => fmt::write(
+                &mut text,
+                format_args!("panic: {} x{}\n", record, record.count),
+            ),
+            None => fmt::write(&mut text, format_args!("panic: none\n")),
+        }
+        .ok();
+        let errors = &self.errors;
+        fmt::write(
+            &mut text,
+            format_args!(
+                "display errors: {}\nrecoveries: {}\nspawn errors: {}\nserial errors: {}\n\
+                 log overflows: {}",
+                errors.display,
+                errors.recoveries,
+                errors.spawn,
+                errors.serial,
+                errors.log_overflows
+            ),
+        )
+        .ok();
+        draw_rows(target, "- Diagnostics -", text.lines())
+    }
+}
+
+/// Show a panic with its location, e.g. `main.rs:12`, and the message in as many rows as fit in
+/// the display
+pub fn draw_panic<D>(target: &mut D, location: &str, message: &str) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    // only the ASCII is in the font and the line ends are in the wrap
+    let mut chars = message.chars().map(|c| match c {
+        ' '..='~' => c,
+        '\n' | '\r' | '\t' => ' ',
+        _ => '?',
+    });
+    let mut rows: [String<COLS>; ROWS - 2] = Default::default();
+    for row in rows.iter_mut() {
+        *row = chars.by_ref().take(COLS).collect();
+    }
+    draw_rows(
+        target,
+        "!!! PANIC !!!",
+        Some(location)
+            .into_iter()
+            .chain(rows.iter().map(|row| row.as_str())),
+    )
+}
+
+/// a title and the `rows` below it in the small font, the rows that don't fit are not shown
+fn draw_rows<'a, D>(
+    target: &mut D,
+    title: &'a str,
+    rows: impl Iterator<Item = &'a str>,
+) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let style = MonoTextStyle::new(&FONT_6X9, BinaryColor::On);
+    for (index, row) in Some(title).into_iter().chain(rows).take(ROWS).enumerate() {
+        let y = index as i32 * ROW_HEIGHT;
+        Text::with_baseline(row, Point::new(0, y), style, Baseline::Top).draw(target)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::canvas::Canvas;
+
+    #[test]
+    fn test_panic_record() {
+        let record = PanicRecord::new("src/main.rs", 123, None);
+        assert_eq!((record.file(), record.line, record.count), ("main", 123, 1));
+        assert_eq!(PanicRecord::from_bits(record.to_bits()), Some(record));
+        let record = PanicRecord::new("src\\io.rs", 70_000, Some(record));
+        assert_eq!((record.file(), record.line, record.count), ("io", 65535, 2));
+        assert_eq!(PanicRecord::from_bits(record.to_bits()), Some(record));
+        let mut last = record;
+        last.count = 255;
+        assert_eq!(PanicRecord::new("a.rs", 1, Some(last)).count, 255);
+        // the registers of a new backup domain are zero
+        assert_eq!(PanicRecord::from_bits([0; 4]), None);
+        assert_eq!(PanicRecord::from_bits([0xa500, 1, 0, 0]), None);
+        let record = PanicRecord::from_bits([0xa501, 7, 0x6d00, 0]).unwrap();
+        let mut text: String<8> = String::new();
+        fmt::write(&mut text, format_args!("{}", record)).unwrap();
+        assert_eq!(text, "m:7");
+    }
+
+    #[test]
+    fn test_draw_panic() {
+        let mut canvas = Canvas::new();
+        draw_panic(&mut canvas, "main.rs:1", "é\n").unwrap();
+        // the title, the location and one row of the message
+        assert!(canvas.rows().take(10).flatten().any(|&byte| byte != 0));
+        assert!(canvas.rows().skip(30).flatten().all(|&byte| byte == 0));
+        let mut canvas = Canvas::new();
+        let long = core::str::from_utf8(&[b'x'; 100]).unwrap();
+        draw_panic(&mut canvas, "main.rs:1", long).unwrap();
+        assert!(canvas.rows().skip(50).flatten().any(|&byte| byte != 0));
+    }
+}
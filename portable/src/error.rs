@@ -0,0 +1,93 @@
+/// The errors of the firmware that are recovered at runtime and counted in the diagnostics
+use core::fmt;
+
+use crate::log;
+
+/// The errors of the tasks, none of them stops the firmware
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AppError {
+    /// a transfer to the display failed in the I2C bus
+    Display,
+    /// a task couldn't be spawned because its queue was full
+    Spawn,
+    /// the serial console or the log lost data
+    Serial(log::Error),
+}
+
+impl AppError {
+    pub fn description(self) -> &'static str {
+        use self::AppError::*;
+        match self {
+            Display => "display not responding",
+            Spawn => "task queue full",
+            Serial(err) => err.description(),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.description())
+    }
+}
+
+impl From<log::Error> for AppError {
+    fn from(err: log::Error) -> Self {
+        AppError::Serial(err)
+    }
+}
+
+/// How many times each error happened since the boot, they stop at `u16::MAX`
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ErrorCounters {
+    pub display: u16,
+    pub spawn: u16,
+    pub serial: u16,
+    /// the errors of the display fixed by initializing it again
+    pub recoveries: u16,
+    /// the lines lost by the log, a copy of `Logger::overflows`
+    pub log_overflows: u32,
+}
+
+impl ErrorCounters {
+    pub fn count(&mut self, error: AppError) {
+        let counter = match error {
+            AppError::Display => &mut self.display,
+            AppError::Spawn => &mut self.spawn,
+            AppError::Serial(_) => &mut self.serial,
+        };
+        *counter = counter.saturating_add(1);
+    }
+
+    pub fn recovered(&mut self) {
+        self.recoveries = self.recoveries.saturating_add(1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_error_counters() {
+        let mut errors = ErrorCounters::default();
+        errors.count(AppError::Display);
+        errors.count(AppError::from(log::Error::Overrun));
+        errors.count(AppError::Serial(log::Error::Framing));
+        errors.recovered();
+        assert_eq!(
+            errors,
+            ErrorCounters {
+                display: 1,
+                spawn: 0,
+                serial: 2,
+                recoveries: 1,
+                log_overflows: 0,
+            }
+        );
+        errors.spawn = u16::MAX;
+        errors.count(AppError::Spawn);
+        assert_eq!(errors.spawn, u16::MAX);
+        assert_eq!(AppError::Spawn.description(), "task queue full");
+    }
+}
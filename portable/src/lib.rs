@@ -0,0 +1,37 @@
+//----------------------------------------------------------------------------
+// Hardware independent part of the rtic-oled-ui firmware
+//
+// Everything here only depends on `embedded-hal` and `embedded-graphics` traits
+// so it can be reused in other boards and tested in the host with `cargo test`
+//----------------------------------------------------------------------------
+#![deny(unsafe_code)]
+#![no_std]
+
+pub mod alarm;
+pub mod app;
+pub mod buttons;
+pub mod canvas;
+pub mod console;
+pub mod datetime;
+pub mod debounce;
+pub mod diagnostics;
+pub mod error;
+pub mod log;
+pub mod screens;
+pub mod timers;
+pub mod timezone;
+pub mod ui;
+
+pub use alarm::{Alarm, AlarmAction};
+pub use app::{AppFSM, Effect};
+pub use buttons::{Button, Gesture, GestureConfig};
+pub use canvas::Canvas;
+pub use console::{Command, Console};
+pub use datetime::{DateField, DateFormat, DateTime, DayOfWeek};
+pub use debounce::{Debounce, Integrator, Majority, TimeBased};
+pub use diagnostics::{Diagnostics, PanicRecord};
+pub use error::{AppError, ErrorCounters};
+pub use screens::{draw_alarm, draw_clock, AlarmEditor, ClockEditor, TimeZoneEditor};
+pub use timers::{Countdown, Stopwatch};
+pub use timezone::{DstRule, TimeZone};
+pub use ui::{draw_menu, Key, MenuFSM, MenuState, Msg, Screen};
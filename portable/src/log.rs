@@ -0,0 +1,438 @@
+/// Leveled log lines with a timestamp and a tag, they can be written in any serial port
+use core::fmt::{self, Write};
+
+use embedded_hal::serial;
+use heapless::{String, Vec};
+
+use crate::timers::Millis;
+
+/// Levels of the log messages from the most to the least important, the firmware filters them
+/// and the console can change the level
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl Level {
+    /// the start of the log lines
+    pub fn prefix(self) -> &'static str {
+        use self::Level::*;
+        match self {
+            Error => "ERR",
+            Warn => "WRN",
+            Info => "LOG",
+            Debug => "DBG",
+        }
+    }
+
+    /// the name used in the console commands
+    pub fn name(self) -> &'static str {
+        use self::Level::*;
+        match self {
+            Error => "error",
+            Warn => "warn",
+            Info => "info",
+            Debug => "debug",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Level::Error, Level::Warn, Level::Info, Level::Debug]
+            .into_iter()
+            .find(|level| level.name() == name)
+    }
+}
+
+/// the messages less important than this level are removed at compile time
+pub const MAX_LEVEL: Level = if cfg!(feature = "log-debug") {
+    Level::Debug
+} else {
+    Level::Info
+};
+
+/// the longest log line, the longer messages are cut
+pub const MAX_LINE_LEN: usize = 128;
+
+/// how long `send` waits for a full port, a UART at 9600 bauds sends a byte in 1 ms
+pub const SEND_TIMEOUT_MS: Millis = 20;
+
+/// the last part of a `module_path!()`, it is the default tag of the log lines
+pub fn module_tag(path: &str) -> &str {
+    path.rsplit("::").next().unwrap_or(path)
+}
+
+/// The errors of the log and of the serial port that it uses
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// a received byte was lost because the previous one was not read in time
+    Overrun,
+    /// a received byte was corrupted: a framing, noise or parity error
+    Framing,
+    /// there is no room for the data in the buffer of the port, it was lost
+    Overflow,
+    /// the port failed to send a byte
+    Write,
+}
+
+impl Error {
+    pub fn description(self) -> &'static str {
+        use self::Error::*;
+        match self {
+            Overrun => "receive overrun",
+            Framing => "receive framing error",
+            Overflow => "log overflow",
+            Write => "write error",
+        }
+    }
+}
+
+//-------------------------------------------------------------------------
+//                        macros
+//-------------------------------------------------------------------------
+/// write a line in the log with the level, the monotonic time and a tag:
+/// `LOG: [    1.250] app: text`. The tag is the name of the module unless it is given with
+/// `target: "tag"`, e.g. `log!(logger, Level::Info, target: "alarm", "{} rings", 1)`
+#[macro_export]
+macro_rules! log {
+    ($logger:expr, $level:expr, target: $tag:expr, $($arg:tt)+) => {{
+        let level: $crate::log::Level = $level;
+        // the constant comparison removes the disabled levels at compile time
+        if (level as u8) <= ($crate::log::MAX_LEVEL as u8) {
+            $logger.write_line(level, $tag, format_args!($($arg)+))
+        } else {
+            Ok(())
+        }
+    }};
+    ($logger:expr, $level:expr, $($arg:tt)+) => {
+        $crate::log!(
+            $logger,
+            $level,
+            target: $crate::log::module_tag(module_path!()),
+            $($arg)+
+        )
+    };
+}
+
+#[macro_export]
+macro_rules! error {
+    ($logger:expr, $($arg:tt)+) => {
+        $crate::log!($logger, $crate::log::Level::Error, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! warn {
+    ($logger:expr, $($arg:tt)+) => {
+        $crate::log!($logger, $crate::log::Level::Warn, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! info {
+    ($logger:expr, $($arg:tt)+) => {
+        $crate::log!($logger, $crate::log::Level::Info, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! debug {
+    ($logger:expr, $($arg:tt)+) => {
+        $crate::log!($logger, $crate::log::Level::Debug, $($arg)+)
+    };
+}
+
+//-------------------------------------------------------------------------
+//                        logger
+//-------------------------------------------------------------------------
+/// A logger that writes in a serial port, a port with a buffer returns `WouldBlock` when it is
+/// full and the lines that don't fit are lost instead of waiting
+pub struct Logger<W> {
+    out: W,
+    /// the messages less important than this level are ignored
+    level: Level,
+    /// the time of the log lines
+    timestamp: fn() -> Millis,
+    /// the lines and frames lost because the port was full
+    overflows: u32,
+    /// the port didn't take a byte of `send` in `SEND_TIMEOUT_MS`, e.g. RTT without a debug
+    /// probe, the next texts don't wait until a byte is sent again
+    stalled: bool,
+}
+
+impl<W: WriteFrame> Logger<W> {
+    pub fn new(out: W, timestamp: fn() -> Millis) -> Self {
+        Self {
+            out,
+            level: MAX_LEVEL,
+            timestamp,
+            overflows: 0,
+            stalled: false,
+        }
+    }
+
+    /// the serial port, e.g. to check a `Capture` in the tests
+    pub fn port(&self) -> &W {
+        &self.out
+    }
+
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    /// change the level at runtime, it can't be more verbose than `MAX_LEVEL`
+    pub fn set_level(&mut self, level: Level) {
+        self.level = level.min(MAX_LEVEL);
+    }
+
+    /// the lines and frames lost since the start because the port was full
+    pub fn overflows(&self) -> u32 {
+        self.overflows
+    }
+
+    /// write a complete line, use it with the `log!` macros. A line that doesn't fit in the port
+    /// is lost and counted in `overflows`
+    pub fn write_line(
+        &mut self,
+        level: Level,
+        tag: &str,
+        args: fmt::Arguments,
+    ) -> Result<(), Error> {
+        if level > self.level {
+            return Ok(());
+        }
+        let ms = (self.timestamp)();
+        let mut line: String<MAX_LINE_LEN> = String::new();
+        // a message that doesn't fit is cut but the line end is always there
+        write!(
+            &mut line,
+            "{}: [{:5}.{:03}] {}: {}",
+            level.prefix(),
+            ms / 1000,
+            ms % 1000,
+            tag,
+            args
+        )
+        .ok();
+        let mut end = line.len().min(MAX_LINE_LEN - 2);
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+        line.truncate(end);
+        line.push_str("\r\n").ok();
+        self.send_raw(line.as_bytes())
+    }
+
+    /// write a binary frame without changes, a frame that doesn't fit in the port is not written
+    /// at all and it is counted in `overflows`
+    pub fn send_raw(&mut self, frame: &[u8]) -> Result<(), Error> {
+        match self.out.write_frame(frame) {
+            Ok(()) => {}
+            Err(nb::Error::WouldBlock) => {
+                self.overflows = self.overflows.wrapping_add(1);
+                return Err(Error::Overflow);
+            }
+            Err(nb::Error::Other(_)) => return Err(Error::Write),
+        }
+        // a buffered port starts to send, the end is not waited
+        self.out.flush().ok();
+        Ok(())
+    }
+
+    /// write a text waiting for the port if needed, the NUL bytes are skipped. It is for the long
+    /// outputs of the console like the screen dump that don't fit in the buffer of the port. If
+    /// the port doesn't take a byte in `SEND_TIMEOUT_MS` the rest is lost and counted in
+    /// `overflows`
+    pub fn send(&mut self, text: &[u8]) -> Result<(), Error> {
+        for &byte in text {
+            if byte == 0x00 {
+                continue;
+            }
+            let start = (self.timestamp)();
+            loop {
+                match self.out.write(byte) {
+                    Ok(()) => break,
+                    Err(nb::Error::WouldBlock)
+                        if !self.stalled
+                            && (self.timestamp)().wrapping_sub(start) < SEND_TIMEOUT_MS => {}
+                    Err(nb::Error::WouldBlock) => {
+                        self.stalled = true;
+                        self.overflows = self.overflows.wrapping_add(1);
+                        return Err(Error::Overflow);
+                    }
+                    Err(nb::Error::Other(_)) => return Err(Error::Write),
+                }
+            }
+            self.stalled = false;
+        }
+        self.out.flush().ok();
+        Ok(())
+    }
+}
+
+impl<W: WriteFrame> fmt::Write for Logger<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.send(s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
+
+//-------------------------------------------------------------------------
+//                        ports
+//-------------------------------------------------------------------------
+/// A serial port that takes a frame whole or not at all, so a full port never has a part of a
+/// log line or a frame
+pub trait WriteFrame: serial::Write<u8> {
+    /// write all the `frame`, or nothing and `WouldBlock` when it doesn't fit
+    fn write_frame(&mut self, frame: &[u8]) -> nb::Result<(), Self::Error>;
+}
+
+/// A port that writes the log in a `core::fmt::Write`, e.g. a `heapless::String`
+pub struct FmtPort<W>(pub W);
+
+impl<W: fmt::Write> serial::Write<u8> for FmtPort<W> {
+    type Error = fmt::Error;
+
+    fn write(&mut self, byte: u8) -> nb::Result<(), fmt::Error> {
+        self.0
+            .write_char(char::from(byte))
+            .map_err(nb::Error::Other)
+    }
+
+    fn flush(&mut self) -> nb::Result<(), fmt::Error> {
+        Ok(())
+    }
+}
+
+impl<W: fmt::Write> WriteFrame for FmtPort<W> {
+    fn write_frame(&mut self, frame: &[u8]) -> nb::Result<(), fmt::Error> {
+        frame
+            .iter()
+            .try_for_each(|&byte| serial::Write::write(self, byte))
+    }
+}
+
+/// A port that saves `N` bytes in memory so the tests can check the log, it is full like a
+/// buffered port after that
+#[derive(Debug, Default)]
+pub struct Capture<const N: usize> {
+    pub bytes: Vec<u8, N>,
+}
+
+impl<const N: usize> Capture<N> {
+    pub fn new() -> Self {
+        Self { bytes: Vec::new() }
+    }
+
+    /// the captured text, empty if it isn't valid UTF-8
+    pub fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.bytes).unwrap_or("")
+    }
+}
+
+impl<const N: usize> serial::Write<u8> for Capture<N> {
+    type Error = core::convert::Infallible;
+
+    fn write(&mut self, byte: u8) -> nb::Result<(), Self::Error> {
+        self.bytes.push(byte).map_err(|_| nb::Error::WouldBlock)
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<const N: usize> WriteFrame for Capture<N> {
+    fn write_frame(&mut self, frame: &[u8]) -> nb::Result<(), Self::Error> {
+        self.bytes
+            .extend_from_slice(frame)
+            .map_err(|_| nb::Error::WouldBlock)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_level() {
+        assert!(Level::Error < Level::Warn && Level::Info < Level::Debug);
+        assert_eq!(Level::from_name("warn"), Some(Level::Warn));
+        assert_eq!(Level::from_name("trace"), None);
+        assert_eq!(Level::Info.prefix(), "LOG");
+        assert_eq!(module_tag("rtic_oled_ui::app"), "app");
+        assert_eq!(module_tag("main"), "main");
+    }
+
+    #[test]
+    fn test_logger() {
+        let mut logger = Logger::new(Capture::<128>::new(), || 61_250);
+        crate::info!(logger, "{} + {}", 1, 2).unwrap();
+        crate::warn!(logger, target: "alarm", "snoozed").unwrap();
+        // without the `log-debug` feature the debug messages are also removed at compile time
+        logger.set_level(Level::Info);
+        crate::debug!(logger, "hidden").unwrap();
+        logger.set_level(Level::Warn);
+        crate::info!(logger, "hidden").unwrap();
+        assert_eq!(
+            logger.port().as_str(),
+            "LOG: [   61.250] test: 1 + 2\r\nWRN: [   61.250] alarm: snoozed\r\n"
+        );
+    }
+
+    #[test]
+    fn test_logger_long_line() {
+        let mut logger = Logger::new(Capture::<256>::new(), || 0);
+        // the cut of a long line falls in the middle of a character
+        let text: String<128> = core::iter::repeat_n('ñ', 52).collect();
+        crate::info!(logger, "{}", text).unwrap();
+        let line = logger.port().as_str();
+        assert!(line.starts_with("LOG: [    0.000] test: ññ"));
+        assert!(line.ends_with("ñ\r\n"));
+        assert_eq!(line.len(), MAX_LINE_LEN - 1);
+    }
+
+    #[test]
+    fn test_logger_overflow() {
+        let mut logger = Logger::new(Capture::<64>::new(), || 0);
+        crate::info!(logger, "first").unwrap();
+        // a line that doesn't fit is lost whole and a shorter one still fits
+        assert_eq!(crate::info!(logger, "second line"), Err(Error::Overflow));
+        assert_eq!(logger.overflows(), 1);
+        crate::info!(logger, "third").unwrap();
+        assert_eq!(
+            logger.port().as_str(),
+            "LOG: [    0.000] test: first\r\nLOG: [    0.000] test: third\r\n"
+        );
+        // the frames are binary safe and the text skips the NUL
+        let mut logger = Logger::new(Capture::<8>::new(), || 0);
+        logger.send_raw(&[0x7e, 0x00, 0x01]).unwrap();
+        logger.send(b"a\0b").unwrap();
+        assert_eq!(logger.port().bytes, [0x7e, 0x00, 0x01, b'a', b'b']);
+    }
+
+    #[test]
+    fn test_send_timeout() {
+        use core::sync::atomic::{AtomicU32, Ordering};
+        // a clock that advances 1 ms in each read
+        static NOW: AtomicU32 = AtomicU32::new(0);
+        let mut logger = Logger::new(Capture::<4>::new(), || NOW.fetch_add(1, Ordering::Relaxed));
+        assert_eq!(logger.send(b"abcdef"), Err(Error::Overflow));
+        assert_eq!(logger.port().as_str(), "abcd");
+        assert_eq!(logger.overflows(), 1);
+        // a stalled port doesn't wait again
+        let now = NOW.load(Ordering::Relaxed);
+        assert_eq!(write!(logger, "g"), Err(fmt::Error));
+        assert_eq!(logger.overflows(), 2);
+        assert!(NOW.load(Ordering::Relaxed) - now <= 1);
+    }
+
+    #[test]
+    fn test_fmt_port() {
+        let mut logger = Logger::new(FmtPort(String::<64>::new()), || 5);
+        crate::error!(logger, target: "i2c", "nack").unwrap();
+        write!(logger, "raw").unwrap();
+        assert_eq!(logger.port().0, "ERR: [    0.005] i2c: nack\r\nraw");
+    }
+}
@@ -0,0 +1,477 @@
+/// Screens with its own state that are edited with the buttons
+use core::fmt::Write;
+
+use embedded_graphics::{
+    mono_font::{ascii::FONT_10X20, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    text::{Alignment, Text},
+};
+use heapless::String;
+
+use crate::alarm::Alarm;
+use crate::datetime::{wrap, DateField, DateFormat, DateTime, DayOfWeek};
+use crate::timezone::TimeZone;
+use crate::ui::{text_styles, Key, Msg, DISPLAY_WIDTH};
+
+/// width in pixels of a `FONT_9X15` character
+const CHAR_WIDTH: i32 = 9;
+
+/// draw `text` in the column `col`(in characters) of the row with baseline `y`
+pub(crate) fn draw_field<D>(
+    target: &mut D,
+    text: &str,
+    col: i32,
+    y: i32,
+    focused: bool,
+) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let (normal, background) = text_styles();
+    let style = if focused { background } else { normal };
+    Text::new(text, Point::new(col * CHAR_WIDTH, y), style).draw(target)?;
+    Ok(())
+}
+
+//-------------------------------------------------------------------------
+//                        clock
+//-------------------------------------------------------------------------
+/// The time in a big font and below the date and the day of the week
+pub fn draw_clock<D>(target: &mut D, datetime: &DateTime) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let (normal, _) = text_styles();
+    let big = MonoTextStyle::new(&FONT_10X20, BinaryColor::On);
+    let center = DISPLAY_WIDTH / 2;
+    let time: String<8> = datetime.format(DateFormat::Time24).unwrap_or_default();
+    Text::with_alignment(&time, Point::new(center, 16), big, Alignment::Center).draw(target)?;
+    let date: String<10> = datetime.format(DateFormat::IsoDate).unwrap_or_default();
+    Text::with_alignment(&date, Point::new(center, 38), normal, Alignment::Center).draw(target)?;
+    let day = datetime.day_of_week.name();
+    Text::with_alignment(day, Point::new(center, 58), normal, Alignment::Center).draw(target)?;
+    Ok(())
+}
+
+//-------------------------------------------------------------------------
+//                        clock editor
+//-------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ClockField {
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+}
+
+impl ClockField {
+    fn next(self) -> Option<Self> {
+        use self::ClockField::*;
+        match self {
+            Year => Some(Month),
+            Month => Some(Day),
+            Day => Some(Hour),
+            Hour => Some(Minute),
+            Minute => None,
+        }
+    }
+}
+
+/// Edit the date and time field by field: Up and Down change the focused field and Enter goes to
+/// the next one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClockEditor {
+    pub datetime: DateTime,
+    pub field: ClockField,
+}
+
+impl ClockEditor {
+    pub fn new(datetime: DateTime) -> Self {
+        Self {
+            datetime,
+            field: ClockField::Year,
+        }
+    }
+
+    fn change(&mut self, delta: i32) {
+        let field = match self.field {
+            ClockField::Year => DateField::Year,
+            ClockField::Month => DateField::Month,
+            ClockField::Day => DateField::Day,
+            ClockField::Hour => DateField::Hour,
+            ClockField::Minute => DateField::Minute,
+        };
+        self.datetime = self.datetime.roll(field, delta);
+    }
+
+    /// update the editor with a button message, when the last field is accepted returns the
+    /// epoch seconds of the new time
+    pub fn update(&mut self, msg: Msg) -> Option<u32> {
+        match msg {
+            Msg::Up | Msg::LongPress(Key::Up) | Msg::Repeat(Key::Up) => self.change(1),
+            Msg::Down | Msg::LongPress(Key::Down) | Msg::Repeat(Key::Down) => self.change(-1),
+            Msg::Enter => match self.field.next() {
+                Some(field) => self.field = field,
+                None => {
+                    self.datetime.sec = 0;
+                    match self.datetime.to_epoch() {
+                        Some(epoch) => {
+                            self.datetime = DateTime::new(epoch);
+                            return Some(epoch);
+                        }
+                        // start again if the date is not valid
+                        None => self.field = ClockField::Year,
+                    }
+                }
+            },
+            _ => {}
+        }
+        None
+    }
+
+    pub fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        use self::ClockField::*;
+        let dt = &self.datetime;
+        let mut text: String<8> = String::new();
+        draw_field(target, "Set clock", 0, 13, false)?;
+        let fields = [
+            (Year, dt.year, 0, 33),
+            (Month, u16::from(dt.month), 5, 33),
+            (Day, u16::from(dt.day), 8, 33),
+            (Hour, u16::from(dt.hour), 0, 53),
+            (Minute, u16::from(dt.min), 3, 53),
+        ];
+        for (field, value, col, y) in fields {
+            text.clear();
+            write!(&mut text, "{:02}", value).ok();
+            draw_field(target, &text, col, y, field == self.field)?;
+        }
+        draw_field(target, "-", 4, 33, false)?;
+        draw_field(target, "-", 7, 33, false)?;
+        draw_field(target, ":", 2, 53, false)?;
+        Ok(())
+    }
+}
+
+//-------------------------------------------------------------------------
+//                        time zone editor
+//-------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TimeZoneField {
+    Offset,
+    Dst,
+}
+
+/// Edit the UTC offset in steps of 15 minutes and the daylight saving time rule, Enter goes to
+/// the next field
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TimeZoneEditor {
+    pub timezone: TimeZone,
+    pub field: TimeZoneField,
+}
+
+impl TimeZoneEditor {
+    pub fn new(timezone: TimeZone) -> Self {
+        Self {
+            timezone,
+            field: TimeZoneField::Offset,
+        }
+    }
+
+    fn change(&mut self, delta: i16) {
+        let tz = &mut self.timezone;
+        match self.field {
+            TimeZoneField::Offset => {
+                let (min, max) = (TimeZone::MIN_OFFSET, TimeZone::MAX_OFFSET);
+                let step = TimeZone::OFFSET_STEP;
+                let quarters = wrap(
+                    i32::from(tz.offset / step),
+                    i32::from(delta),
+                    i32::from(min / step),
+                    i32::from(max / step),
+                );
+                tz.offset = quarters as i16 * step;
+            }
+            TimeZoneField::Dst if delta > 0 => tz.dst = tz.dst.next(),
+            TimeZoneField::Dst => tz.dst = tz.dst.previous(),
+        }
+    }
+
+    /// update the editor with a button message, when the last field is accepted returns the
+    /// new time zone
+    pub fn update(&mut self, msg: Msg) -> Option<TimeZone> {
+        match msg {
+            Msg::Up | Msg::LongPress(Key::Up) | Msg::Repeat(Key::Up) => self.change(1),
+            Msg::Down | Msg::LongPress(Key::Down) | Msg::Repeat(Key::Down) => self.change(-1),
+            Msg::Enter => match self.field {
+                TimeZoneField::Offset => self.field = TimeZoneField::Dst,
+                TimeZoneField::Dst => return Some(self.timezone),
+            },
+            _ => {}
+        }
+        None
+    }
+
+    pub fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let mut text: String<12> = String::new();
+        write!(&mut text, "{}", self.timezone).ok();
+        draw_field(target, "Time zone", 0, 13, false)?;
+        draw_field(target, &text[..3], 0, 33, false)?;
+        let focused = self.field == TimeZoneField::Offset;
+        draw_field(target, &text[3..], 4, 33, focused)?;
+        draw_field(target, "DST", 0, 53, false)?;
+        let focused = self.field == TimeZoneField::Dst;
+        draw_field(target, self.timezone.dst.name(), 4, 53, focused)?;
+        Ok(())
+    }
+}
+
+//-------------------------------------------------------------------------
+//                        alarms
+//-------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AlarmField {
+    Enabled,
+    Hour,
+    Minute,
+    Day(DayOfWeek),
+}
+
+impl AlarmField {
+    fn next(self) -> Option<Self> {
+        use self::AlarmField::*;
+        match self {
+            Enabled => Some(Hour),
+            Hour => Some(Minute),
+            Minute => Some(Day(DayOfWeek::Monday)),
+            Day(DayOfWeek::Sunday) => None,
+            Day(day) => Some(Day(day.next())),
+        }
+    }
+}
+
+/// Edit one of the alarms: Up and Down change the focused field(or toggle the day of the week)
+/// and Enter goes to the next one. An enabled alarm needs a day, without it the last Enter goes
+/// back to the first day
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AlarmEditor {
+    pub index: usize,
+    pub alarm: Alarm,
+    pub field: AlarmField,
+}
+
+impl AlarmEditor {
+    pub fn new(index: usize, alarm: Alarm) -> Self {
+        Self {
+            index,
+            alarm,
+            field: AlarmField::Enabled,
+        }
+    }
+
+    fn change(&mut self, delta: i32) {
+        let alarm = &mut self.alarm;
+        match self.field {
+            AlarmField::Enabled => alarm.enabled = !alarm.enabled,
+            AlarmField::Hour => alarm.hour = wrap(i32::from(alarm.hour), delta, 0, 23) as u8,
+            AlarmField::Minute => alarm.min = wrap(i32::from(alarm.min), delta, 0, 59) as u8,
+            AlarmField::Day(day) => alarm.toggle_day(day),
+        }
+    }
+
+    /// update the editor with a button message, when the last field is accepted returns the
+    /// new alarm
+    pub fn update(&mut self, msg: Msg) -> Option<Alarm> {
+        match msg {
+            Msg::Up | Msg::LongPress(Key::Up) | Msg::Repeat(Key::Up) => self.change(1),
+            Msg::Down | Msg::LongPress(Key::Down) | Msg::Repeat(Key::Down) => self.change(-1),
+            Msg::Enter => match self.field.next() {
+                Some(field) => self.field = field,
+                // NOTE: an enabled alarm without days would never ring
+                None if self.alarm.enabled && self.alarm.days == 0 => {
+                    self.field = AlarmField::Day(DayOfWeek::Monday);
+                }
+                None => return Some(self.alarm),
+            },
+            _ => {}
+        }
+        None
+    }
+
+    pub fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let alarm = &self.alarm;
+        let mut text: String<12> = String::new();
+        write!(&mut text, "Alarm {}", self.index + 1).ok();
+        draw_field(target, &text, 0, 13, false)?;
+        let enabled = if alarm.enabled { "On" } else { "Off" };
+        draw_field(target, enabled, 10, 13, self.field == AlarmField::Enabled)?;
+        for (field, value, col) in [
+            (AlarmField::Hour, alarm.hour, 0),
+            (AlarmField::Minute, alarm.min, 3),
+        ] {
+            text.clear();
+            write!(&mut text, "{:02}", value).ok();
+            draw_field(target, &text, col, 33, field == self.field)?;
+        }
+        draw_field(target, ":", 2, 33, false)?;
+        let mut day = DayOfWeek::Monday;
+        for col in 0..7 {
+            let name = if alarm.rings_on(day) {
+                &day.name()[..1]
+            } else {
+                "-"
+            };
+            draw_field(target, name, col, 53, self.field == AlarmField::Day(day))?;
+            day = day.next();
+        }
+        Ok(())
+    }
+}
+
+/// The screen of a ringing alarm with the current time
+pub fn draw_alarm<D>(target: &mut D, now: &DateTime) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let (normal, _) = text_styles();
+    let big = MonoTextStyle::new(&FONT_10X20, BinaryColor::On);
+    let center = DISPLAY_WIDTH / 2;
+    let time: String<8> = now.format(DateFormat::Time24).unwrap_or_default();
+    Text::with_alignment(&time, Point::new(center, 16), big, Alignment::Center).draw(target)?;
+    Text::with_alignment(
+        "Enter: stop",
+        Point::new(center, 38),
+        normal,
+        Alignment::Center,
+    )
+    .draw(target)?;
+    Text::with_alignment(
+        "Up/Dn: snooze",
+        Point::new(center, 58),
+        normal,
+        Alignment::Center,
+    )
+    .draw(target)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::datetime::DayOfWeek;
+
+    #[test]
+    fn test_clock_editor() {
+        // 2024-03-31 23:59
+        let mut editor = ClockEditor::new(DateTime::new(1711929540));
+        assert_eq!(editor.datetime.day, 31);
+        editor.update(Msg::Down);
+        assert_eq!(editor.datetime.year, 2023);
+        editor.update(Msg::Enter);
+        editor.update(Msg::Down);
+        // february of 2023 only has 28 days
+        assert_eq!((editor.datetime.month, editor.datetime.day), (2, 28));
+        editor.update(Msg::Enter);
+        editor.update(Msg::Repeat(Key::Up));
+        assert_eq!(editor.datetime.day, 1);
+        editor.update(Msg::Enter);
+        editor.update(Msg::Up);
+        assert_eq!(editor.datetime.hour, 0);
+        editor.update(Msg::Enter);
+        editor.update(Msg::Down);
+        assert_eq!(editor.datetime.min, 58);
+        let epoch = editor.update(Msg::Enter).expect("valid date");
+        assert_eq!(
+            DateTime::new(epoch),
+            DateTime {
+                year: 2023,
+                month: 2,
+                day: 1,
+                hour: 0,
+                min: 58,
+                sec: 0,
+                day_of_week: DayOfWeek::Wednesday,
+            }
+        );
+    }
+
+    #[test]
+    fn test_timezone_editor() {
+        use crate::timezone::DstRule;
+        let mut editor = TimeZoneEditor::new(TimeZone::UTC);
+        editor.update(Msg::Down);
+        assert_eq!(editor.timezone.offset, -15);
+        editor.update(Msg::Up);
+        editor.update(Msg::Repeat(Key::Up));
+        editor.update(Msg::Repeat(Key::Up));
+        editor.update(Msg::Repeat(Key::Up));
+        editor.update(Msg::Repeat(Key::Up));
+        assert_eq!(editor.timezone.offset, 60);
+        assert_eq!(editor.update(Msg::Enter), None);
+        editor.update(Msg::Down);
+        assert_eq!(editor.timezone.dst, DstRule::UnitedStates);
+        editor.update(Msg::Up);
+        assert_eq!(
+            editor.update(Msg::Enter),
+            Some(TimeZone::new(60, DstRule::Off))
+        );
+        // the offset wraps from +14:00 to -12:00
+        let mut editor = TimeZoneEditor::new(TimeZone::new(TimeZone::MAX_OFFSET, DstRule::Off));
+        editor.update(Msg::Up);
+        assert_eq!(editor.timezone.offset, TimeZone::MIN_OFFSET);
+    }
+
+    #[test]
+    fn test_alarm_editor() {
+        let mut editor = AlarmEditor::new(0, Alarm::default());
+        editor.update(Msg::Up);
+        assert!(editor.alarm.enabled);
+        editor.update(Msg::Enter);
+        editor.update(Msg::Down);
+        editor.update(Msg::Enter);
+        editor.update(Msg::Repeat(Key::Up));
+        editor.update(Msg::Enter);
+        // toggle monday and sunday
+        editor.update(Msg::Up);
+        for _ in 0..6 {
+            assert_eq!(editor.update(Msg::Enter), None);
+        }
+        editor.update(Msg::Down);
+        let alarm = editor.update(Msg::Enter).expect("last field");
+        assert_eq!((alarm.hour, alarm.min), (23, 1));
+        assert_eq!(
+            alarm.days,
+            1 << DayOfWeek::Monday as u8 | 1 << DayOfWeek::Sunday as u8
+        );
+    }
+
+    #[test]
+    fn test_alarm_editor_without_days() {
+        let mut editor = AlarmEditor::new(1, Alarm::default());
+        editor.update(Msg::Up);
+        for _ in 0..9 {
+            assert_eq!(editor.update(Msg::Enter), None);
+        }
+        // an enabled alarm without days is not accepted
+        assert_eq!(editor.update(Msg::Enter), None);
+        assert_eq!(editor.field, AlarmField::Day(DayOfWeek::Monday));
+        editor.update(Msg::Up);
+        let alarm = (0..7).find_map(|_| editor.update(Msg::Enter));
+        assert_eq!(alarm.map(|alarm| alarm.days), Some(1));
+        // a disabled one doesn't need them
+        let mut editor = AlarmEditor::new(1, Alarm::default());
+        let alarm = (0..10).find_map(|_| editor.update(Msg::Enter));
+        assert_eq!(alarm, Some(Alarm::default()));
+    }
+}
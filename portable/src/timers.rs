@@ -0,0 +1,369 @@
+/// Countdown timer and stopwatch, the times are milliseconds of a monotonic clock
+use core::fmt::Write;
+
+use embedded_graphics::{
+    mono_font::{ascii::FONT_10X20, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    text::{Alignment, Text},
+};
+use heapless::{String, Vec};
+
+use crate::datetime::wrap;
+use crate::screens::draw_field;
+use crate::ui::{text_styles, Key, Msg, DISPLAY_WIDTH};
+
+/// milliseconds of the monotonic clock, they wrap after 49 days so only the differences are used
+pub type Millis = u32;
+
+/// write `ms` as `MM:SS`, the minutes wrap at 100
+fn write_minutes<W: Write>(out: &mut W, ms: Millis) -> core::fmt::Result {
+    let seconds = ms / 1000;
+    write!(out, "{:02}:{:02}", seconds / 60 % 100, seconds % 60)
+}
+
+/// write `ms` as `MM:SS.cc` with the hundredths of second, after an hour as `H:MM:SS` so it
+/// fits in the display
+fn write_hundredths<W: Write>(out: &mut W, ms: Millis) -> core::fmt::Result {
+    let seconds = ms / 1000;
+    match seconds / 3600 {
+        0 => {
+            write_minutes(out, ms)?;
+            write!(out, ".{:02}", ms / 10 % 100)
+        }
+        hours => write!(
+            out,
+            "{}:{:02}:{:02}",
+            hours,
+            seconds / 60 % 60,
+            seconds % 60
+        ),
+    }
+}
+
+//-------------------------------------------------------------------------
+//                        countdown
+//-------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CountdownState {
+    SetMinutes,
+    SetSeconds,
+    Running { end: Millis },
+    Paused { remaining: Millis },
+    Finished,
+}
+
+/// A countdown timer: Up and Down set the minutes and the seconds, then Enter starts, pauses and
+/// resets it. Up or Down resume a paused countdown
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Countdown {
+    pub minutes: u8,
+    pub seconds: u8,
+    pub state: CountdownState,
+}
+
+impl Countdown {
+    pub fn new(minutes: u8, seconds: u8) -> Self {
+        Self {
+            minutes: minutes.min(99),
+            seconds: seconds.min(59),
+            state: CountdownState::SetMinutes,
+        }
+    }
+
+    fn duration(&self) -> Millis {
+        (Millis::from(self.minutes) * 60 + Millis::from(self.seconds)) * 1000
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.state, CountdownState::Running { .. })
+    }
+
+    /// the milliseconds until the end
+    pub fn remaining(&self, now: Millis) -> Millis {
+        use self::CountdownState::*;
+        match self.state {
+            SetMinutes | SetSeconds => self.duration(),
+            Running { end } => match end.wrapping_sub(now) {
+                // `now` is after the `end`
+                remaining if remaining > self.duration() => 0,
+                remaining => remaining,
+            },
+            Paused { remaining } => remaining,
+            Finished => 0,
+        }
+    }
+
+    /// check the end of a running countdown, returns true only once when it finishes
+    pub fn poll(&mut self, now: Millis) -> bool {
+        if self.is_running() && self.remaining(now) == 0 {
+            self.state = CountdownState::Finished;
+            return true;
+        }
+        false
+    }
+
+    pub fn update(&mut self, msg: Msg, now: Millis) {
+        use self::CountdownState::*;
+        let delta = match msg {
+            Msg::Up | Msg::LongPress(Key::Up) | Msg::Repeat(Key::Up) => 1,
+            Msg::Down | Msg::LongPress(Key::Down) | Msg::Repeat(Key::Down) => -1,
+            _ => 0,
+        };
+        self.state = match (self.state, msg) {
+            (SetMinutes, Msg::Enter) => SetSeconds,
+            (SetMinutes, _) => {
+                self.minutes = wrap(i32::from(self.minutes), delta, 0, 99) as u8;
+                SetMinutes
+            }
+            (SetSeconds, Msg::Enter) if self.duration() == 0 => SetMinutes,
+            (SetSeconds, Msg::Enter) => Running {
+                end: now.wrapping_add(self.duration()),
+            },
+            (SetSeconds, _) => {
+                self.seconds = wrap(i32::from(self.seconds), delta, 0, 59) as u8;
+                SetSeconds
+            }
+            (Running { .. }, Msg::Enter) => Paused {
+                remaining: self.remaining(now),
+            },
+            (Paused { remaining }, _) if delta != 0 => Running {
+                end: now.wrapping_add(remaining),
+            },
+            (Paused { .. } | Finished, Msg::Enter) => SetMinutes,
+            (state, _) => state,
+        };
+    }
+
+    pub fn draw<D>(&self, target: &mut D, now: Millis) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        use self::CountdownState::*;
+        let mut text: String<8> = String::new();
+        draw_field(target, "Timer", 0, 13, false)?;
+        match self.state {
+            SetMinutes | SetSeconds => {
+                for (value, col, focused) in [
+                    (self.minutes, 0, self.state == SetMinutes),
+                    (self.seconds, 3, self.state == SetSeconds),
+                ] {
+                    text.clear();
+                    write!(&mut text, "{:02}", value).ok();
+                    draw_field(target, &text, col, 33, focused)?;
+                }
+                draw_field(target, ":", 2, 33, false)?;
+            }
+            _ => {
+                // round up so the countdown shows `00:00` only at the end
+                write_minutes(&mut text, self.remaining(now) + 999).ok();
+                draw_field(target, &text, 0, 33, false)?;
+            }
+        }
+        let status = match self.state {
+            SetMinutes | SetSeconds => "Enter: next",
+            Running { .. } => "Running",
+            Paused { .. } => "Paused",
+            Finished => "Time up!",
+        };
+        draw_field(target, status, 0, 53, self.state == Finished)
+    }
+}
+
+impl Default for Countdown {
+    fn default() -> Self {
+        Self::new(1, 0)
+    }
+}
+
+//-------------------------------------------------------------------------
+//                        stopwatch
+//-------------------------------------------------------------------------
+/// how many of the last laps are saved
+pub const MAX_LAPS: usize = 2;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Lap {
+    /// the first lap is 1
+    pub number: u16,
+    pub duration: Millis,
+}
+
+/// A stopwatch: Enter starts and stops it, Up saves a lap while it runs and Down resets it when
+/// it is stopped
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Stopwatch {
+    /// the time accumulated before the last start
+    elapsed: Millis,
+    start: Option<Millis>,
+    /// the elapsed time of the last lap
+    last_split: Millis,
+    /// the last laps, the newest is the last one
+    pub laps: Vec<Lap, MAX_LAPS>,
+}
+
+impl Stopwatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.start.is_some()
+    }
+
+    /// the running time, it wraps like the clock after 49 days
+    pub fn elapsed(&self, now: Millis) -> Millis {
+        match self.start {
+            Some(start) => self.elapsed.wrapping_add(now.wrapping_sub(start)),
+            None => self.elapsed,
+        }
+    }
+
+    pub fn update(&mut self, msg: Msg, now: Millis) {
+        match (self.start, msg) {
+            (None, Msg::Enter) => self.start = Some(now),
+            (Some(_), Msg::Enter) => {
+                self.elapsed = self.elapsed(now);
+                self.start = None;
+            }
+            (Some(_), Msg::Up) => {
+                let elapsed = self.elapsed(now);
+                let number = self.laps.last().map_or(1, |lap| lap.number.wrapping_add(1));
+                if self.laps.is_full() {
+                    self.laps.remove(0);
+                }
+                self.laps
+                    .push(Lap {
+                        number,
+                        duration: elapsed.wrapping_sub(self.last_split),
+                    })
+                    .ok();
+                self.last_split = elapsed;
+            }
+            (None, Msg::Down) => *self = Self::new(),
+            _ => {}
+        }
+    }
+
+    pub fn draw<D>(&self, target: &mut D, now: Millis) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let big = MonoTextStyle::new(&FONT_10X20, BinaryColor::On);
+        let (normal, _) = text_styles();
+        let mut text: String<20> = String::new();
+        write_hundredths(&mut text, self.elapsed(now)).ok();
+        let center = Point::new(DISPLAY_WIDTH / 2, 16);
+        Text::with_alignment(&text, center, big, Alignment::Center).draw(target)?;
+        for (lap, y) in self.laps.iter().rev().zip([38, 58]) {
+            text.clear();
+            write!(&mut text, "L{} ", lap.number).ok();
+            write_hundredths(&mut text, lap.duration).ok();
+            Text::new(&text, Point::new(0, y), normal).draw(target)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_countdown() {
+        let mut countdown = Countdown::new(0, 58);
+        countdown.update(Msg::Up, 0);
+        countdown.update(Msg::Enter, 0);
+        countdown.update(Msg::Repeat(Key::Up), 0);
+        countdown.update(Msg::Repeat(Key::Up), 0);
+        assert_eq!((countdown.minutes, countdown.seconds), (1, 0));
+        countdown.update(Msg::Enter, 1000);
+        assert!(countdown.is_running());
+        assert_eq!(countdown.remaining(31_000), 30_000);
+        countdown.update(Msg::Enter, 31_000);
+        assert_eq!(countdown.remaining(50_000), 30_000);
+        // Up or Down resume it
+        countdown.update(Msg::Down, 50_000);
+        assert!(!countdown.poll(79_999));
+        assert!(countdown.poll(80_000));
+        assert!(!countdown.poll(80_001));
+        assert_eq!(countdown.state, CountdownState::Finished);
+        countdown.update(Msg::Enter, 81_000);
+        assert_eq!(countdown.state, CountdownState::SetMinutes);
+        assert_eq!(countdown.remaining(81_000), 60_000);
+        // Enter starts, pauses and resets it
+        countdown.update(Msg::Enter, 0);
+        countdown.update(Msg::Enter, 0);
+        countdown.update(Msg::Enter, 10);
+        assert_eq!(
+            countdown.state,
+            CountdownState::Paused { remaining: 59_990 }
+        );
+        countdown.update(Msg::Enter, 20);
+        assert_eq!(countdown.state, CountdownState::SetMinutes);
+        // a zero countdown doesn't start
+        let mut countdown = Countdown::new(0, 0);
+        countdown.update(Msg::Enter, 0);
+        countdown.update(Msg::Enter, 0);
+        assert_eq!(countdown.state, CountdownState::SetMinutes);
+    }
+
+    #[test]
+    fn test_stopwatch() {
+        let mut stopwatch = Stopwatch::new();
+        // the time wraps in the middle
+        let start = u32::MAX - 500;
+        stopwatch.update(Msg::Enter, start);
+        stopwatch.update(Msg::Up, start.wrapping_add(1_000));
+        stopwatch.update(Msg::Up, start.wrapping_add(3_500));
+        stopwatch.update(Msg::Enter, start.wrapping_add(4_000));
+        assert_eq!(stopwatch.elapsed(start.wrapping_add(9_000)), 4_000);
+        stopwatch.update(Msg::Enter, 10_000);
+        stopwatch.update(Msg::Up, 11_000);
+        let laps: Vec<(u16, Millis), MAX_LAPS> = stopwatch
+            .laps
+            .iter()
+            .map(|lap| (lap.number, lap.duration))
+            .collect();
+        assert_eq!(laps, [(2, 2_500), (3, 1_500)]);
+        // reset only when stopped
+        stopwatch.update(Msg::Down, 12_000);
+        assert!(stopwatch.is_running());
+        stopwatch.update(Msg::Enter, 12_000);
+        stopwatch.update(Msg::Down, 12_000);
+        assert_eq!(stopwatch, Stopwatch::new());
+    }
+
+    #[test]
+    fn test_stopwatch_hours() {
+        let mut text: String<20> = String::new();
+        write_hundredths(&mut text, 3_599_990).unwrap();
+        assert_eq!(text, "59:59.99");
+        // the hours don't wrap the minutes at 100
+        text.clear();
+        write_hundredths(&mut text, 6_000_000).unwrap();
+        assert_eq!(text, "1:40:00");
+        text.clear();
+        write_hundredths(&mut text, u32::MAX).unwrap();
+        assert_eq!(text, "1193:02:47");
+    }
+
+    #[test]
+    fn test_stopwatch_wrap() {
+        let mut stopwatch = Stopwatch::new();
+        stopwatch.update(Msg::Enter, 0);
+        stopwatch.update(Msg::Up, u32::MAX);
+        stopwatch.update(Msg::Enter, u32::MAX);
+        stopwatch.update(Msg::Enter, 0);
+        assert_eq!(stopwatch.elapsed(2), 1);
+        stopwatch.update(Msg::Up, 2);
+        assert_eq!(stopwatch.laps.last().map(|lap| lap.duration), Some(2));
+    }
+
+    #[test]
+    fn test_time_format() {
+        let mut text: String<16> = String::new();
+        write_hundredths(&mut text, 754_321).unwrap();
+        assert_eq!(text, "12:34.32");
+    }
+}
@@ -0,0 +1,231 @@
+/// Conversion between the UTC time of the RTC and the local time that is displayed
+use core::fmt;
+
+use crate::datetime::{DateTime, DayOfWeek};
+
+/// Daylight saving time rules, all of them add one hour in the summer
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DstRule {
+    /// no daylight saving time
+    Off,
+    /// from the last Sunday of March to the last Sunday of October at 01:00 UTC
+    Europe,
+    /// from the second Sunday of March to the first Sunday of November at 02:00 local time
+    UnitedStates,
+}
+
+impl DstRule {
+    pub fn next(self) -> Self {
+        use self::DstRule::*;
+        match self {
+            Off => Europe,
+            Europe => UnitedStates,
+            UnitedStates => Off,
+        }
+    }
+
+    pub fn previous(self) -> Self {
+        self.next().next()
+    }
+
+    pub fn name(self) -> &'static str {
+        use self::DstRule::*;
+        match self {
+            Off => "Off",
+            Europe => "EU",
+            UnitedStates => "US",
+        }
+    }
+}
+
+/// epoch seconds of the midnight of a date, the `day_of_week` is not needed for that
+fn midnight(year: u16, month: u8, day: u8) -> Option<u32> {
+    DateTime {
+        year,
+        month,
+        day,
+        hour: 0,
+        min: 0,
+        sec: 0,
+        day_of_week: DayOfWeek::Monday,
+    }
+    .to_epoch()
+}
+
+/// epoch seconds of the midnight of the `nth`(starting from 1) Sunday of a month
+fn nth_sunday(year: u16, month: u8, nth: u32) -> Option<u32> {
+    let first = midnight(year, month, 1)?;
+    let forward = DayOfWeek::Sunday as u32 - DateTime::new(first).day_of_week as u32;
+    Some(first + (forward + 7 * (nth - 1)) * 86400)
+}
+
+/// epoch seconds of the midnight of the last Sunday of a month
+fn last_sunday(year: u16, month: u8) -> Option<u32> {
+    let next_month = match month {
+        12 => midnight(year + 1, 1, 1)?,
+        _ => midnight(year, month + 1, 1)?,
+    };
+    let last = DateTime::new(next_month - 86400);
+    let back = (last.day_of_week as u32 + 1) % 7;
+    Some(next_month - (back + 1) * 86400)
+}
+
+/// A fixed offset from UTC plus an optional daylight saving time rule
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TimeZone {
+    /// minutes from UTC without the daylight saving time
+    pub offset: i16,
+    pub dst: DstRule,
+}
+
+impl TimeZone {
+    pub const UTC: TimeZone = TimeZone {
+        offset: 0,
+        dst: DstRule::Off,
+    };
+    pub const MIN_OFFSET: i16 = -12 * 60;
+    pub const MAX_OFFSET: i16 = 14 * 60;
+    /// the offsets are multiples of a quarter of hour
+    pub const OFFSET_STEP: i16 = 15;
+
+    pub fn new(offset: i16, dst: DstRule) -> Self {
+        Self {
+            offset: offset.clamp(Self::MIN_OFFSET, Self::MAX_OFFSET),
+            dst,
+        }
+    }
+
+    /// check if the daylight saving time is active in the `utc` instant
+    pub fn is_dst(&self, utc: u32) -> bool {
+        // the instant to compare and the changes of its year
+        let (now, start, end) = match self.dst {
+            DstRule::Off => return false,
+            // the changes are at the same instant in all the european zones
+            DstRule::Europe => {
+                let year = DateTime::new(utc).year;
+                let start = last_sunday(year, 3).map(|t| t + 3600);
+                (utc, start, last_sunday(year, 10).map(|t| t + 3600))
+            }
+            // 02:00 local in March and 02:00 of the summer time(01:00 standard) in November
+            DstRule::UnitedStates => {
+                let Some(standard) = utc.checked_add_signed(i32::from(self.offset) * 60) else {
+                    return false;
+                };
+                let year = DateTime::new(standard).year;
+                let start = nth_sunday(year, 3, 2).map(|t| t + 7200);
+                (standard, start, nth_sunday(year, 11, 1).map(|t| t + 3600))
+            }
+        };
+        matches!((start, end), (Some(start), Some(end)) if start <= now && now < end)
+    }
+
+    /// seconds to add to `utc` to get the local time
+    pub fn offset_at(&self, utc: u32) -> i64 {
+        let dst = if self.is_dst(utc) { 3600 } else { 0 };
+        i64::from(self.offset) * 60 + dst
+    }
+
+    /// the local time of the `utc` epoch seconds
+    pub fn to_local(&self, utc: u32) -> DateTime {
+        let local = DateTime::new(utc).add_seconds(self.offset_at(utc));
+        local.unwrap_or_else(|| DateTime::new(utc))
+    }
+
+    /// the UTC epoch seconds of a local time, in the repeated hour at the end of the summer the
+    /// first one is used
+    pub fn to_utc(&self, local: &DateTime) -> Option<u32> {
+        let standard = local
+            .add_seconds(-i64::from(self.offset) * 60)?
+            .to_epoch()?;
+        match standard.checked_sub(3600) {
+            Some(summer) if self.is_dst(summer) => Some(summer),
+            _ => Some(standard),
+        }
+    }
+
+    /// pack the time zone in 16 bits to save it in a backup register, zero is UTC
+    pub fn to_bits(self) -> u16 {
+        let quarters = (self.offset / Self::OFFSET_STEP) as i8 as u8;
+        u16::from(quarters) | (self.dst as u16) << 8
+    }
+
+    pub fn from_bits(bits: u16) -> Self {
+        let quarters = i16::from(bits as u8 as i8);
+        let dst = match bits >> 8 {
+            1 => DstRule::Europe,
+            2 => DstRule::UnitedStates,
+            _ => DstRule::Off,
+        };
+        Self::new(quarters * Self::OFFSET_STEP, dst)
+    }
+}
+
+impl Default for TimeZone {
+    fn default() -> Self {
+        Self::UTC
+    }
+}
+
+impl fmt::Display for TimeZone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.offset < 0 { '-' } else { '+' };
+        let offset = self.offset.unsigned_abs();
+        write!(f, "UTC{}{:02}:{:02}", sign, offset / 60, offset % 60)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_timezone() {
+        // 2024-03-31 00:59:59 UTC, one second before the european summer time
+        let utc = 1711846799;
+        let madrid = TimeZone::new(60, DstRule::Europe);
+        assert!(!madrid.is_dst(utc));
+        assert!(madrid.is_dst(utc + 1));
+        assert_eq!(madrid.to_local(utc).hour, 1);
+        assert_eq!(madrid.to_local(utc + 1).hour, 3);
+        // 2024-10-27 00:59:59 UTC, the end of the summer
+        assert!(madrid.is_dst(1729990799));
+        assert!(!madrid.is_dst(1729990800));
+        // 2024-03-10 02:00 EST is 07:00 UTC and 2024-11-03 02:00 EDT is 06:00 UTC
+        let new_york = TimeZone::new(-5 * 60, DstRule::UnitedStates);
+        assert!(!new_york.is_dst(1710053999));
+        assert!(new_york.is_dst(1710054000));
+        assert!(new_york.is_dst(1730613599));
+        assert!(!new_york.is_dst(1730613600));
+        let local = new_york.to_local(1710054000);
+        assert_eq!(
+            (local.day, local.hour, local.day_of_week),
+            (10, 3, DayOfWeek::Sunday)
+        );
+        assert_eq!(new_york.to_utc(&local), Some(1710054000));
+        // the offset can move the date
+        let kiribati = TimeZone::new(14 * 60, DstRule::Off);
+        assert_eq!(kiribati.to_local(utc).day, 31);
+        assert_eq!(kiribati.to_local(utc - 3600).day_of_week, DayOfWeek::Sunday);
+        assert_eq!(TimeZone::UTC.to_local(utc), DateTime::new(utc));
+    }
+
+    #[test]
+    fn test_timezone_bits() {
+        assert_eq!(TimeZone::from_bits(0), TimeZone::UTC);
+        for timezone in [
+            TimeZone::new(-12 * 60, DstRule::Off),
+            TimeZone::new(5 * 60 + 45, DstRule::Europe),
+            TimeZone::new(-3 * 60 - 30, DstRule::UnitedStates),
+        ] {
+            assert_eq!(TimeZone::from_bits(timezone.to_bits()), timezone);
+        }
+    }
+
+    #[test]
+    fn test_timezone_display() {
+        use core::fmt::Write;
+        let mut text: heapless::String<16> = heapless::String::new();
+        write!(&mut text, "{}", TimeZone::new(-3 * 60 - 30, DstRule::Off)).unwrap();
+        assert_eq!(text, "UTC-03:30");
+    }
+}
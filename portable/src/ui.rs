@@ -0,0 +1,576 @@
+/// User interface primitives
+use embedded_graphics::{
+    image::{Image, ImageRawLE},
+    mono_font::{ascii::FONT_9X15, MonoTextStyle, MonoTextStyleBuilder},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{Line, PrimitiveStyle, Rectangle},
+    text::Text,
+};
+use heapless::Vec;
+
+use crate::buttons::Gesture;
+
+pub const DISPLAY_WIDTH: i32 = 128;
+pub const DISPLAY_HEIGHT: i32 = DISPLAY_WIDTH / 2;
+// TODO(elsuizo:2021-11-28): use this constants for a better text positions
+// const CHAR_HEIGHT: i32 = 14;
+// const CHAR_WIDTH: i32 = 6;
+/// baseline of the first row of a menu
+const FIRST_ROW_BASELINE: i32 = 13;
+/// vertical distance between two rows of a menu
+const ROW_HEIGHT: i32 = 20;
+/// how many nested menus and screens can be opened from the main menu
+pub const MAX_DEPTH: usize = 4;
+/// how many rows of `FONT_9X15` fit in the display
+pub const VISIBLE_ROWS: usize = 3;
+/// the scrollbar lives in the last pixels of the display(the labels are 14 chars of 9 pixels)
+const SCROLLBAR_WIDTH: u32 = 2;
+
+//-------------------------------------------------------------------------
+//                        menu tree
+//-------------------------------------------------------------------------
+/// The screens that a leaf of the menu can open
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Screen {
+    Image,
+    SetClock,
+    Clock,
+    TimeZone,
+    /// edit the alarm with this index
+    SetAlarm(usize),
+    /// an alarm is ringing, it is not in the menu
+    Alarm,
+    Countdown,
+    Stopwatch,
+    /// the last panic and the state of the firmware
+    Diagnostics,
+}
+
+impl Screen {
+    /// the screens that use the buttons, only a long press in Enter closes them
+    pub fn handles_input(&self) -> bool {
+        matches!(
+            self,
+            Self::SetClock
+                | Self::TimeZone
+                | Self::SetAlarm(_)
+                | Self::Alarm
+                | Self::Countdown
+                | Self::Stopwatch
+        )
+    }
+}
+
+/// What happens when `Enter` is pressed over a item
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MenuKind {
+    /// go inside a nested menu
+    Submenu(&'static [MenuItem]),
+    /// open a screen
+    Screen(Screen),
+    /// return to the parent menu
+    Back,
+}
+
+/// A entry in the menu tree
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MenuItem {
+    pub label: &'static str,
+    pub kind: MenuKind,
+}
+
+impl MenuItem {
+    pub const fn submenu(label: &'static str, items: &'static [MenuItem]) -> Self {
+        Self {
+            label,
+            kind: MenuKind::Submenu(items),
+        }
+    }
+
+    pub const fn screen(label: &'static str, screen: Screen) -> Self {
+        Self {
+            label,
+            kind: MenuKind::Screen(screen),
+        }
+    }
+
+    pub const fn back(label: &'static str) -> Self {
+        Self {
+            label,
+            kind: MenuKind::Back,
+        }
+    }
+}
+
+/// The menu of the application, adding a entry here is all that is needed to show it
+pub static MAIN_MENU: &[MenuItem] = &[
+    MenuItem::screen("--- Menu 1 ---", Screen::Image),
+    MenuItem::screen("--- Menu 2 ---", Screen::Image),
+    MenuItem::screen("--- Menu 3 ---", Screen::Image),
+    MenuItem::screen("--- Clock ---", Screen::Clock),
+    MenuItem::screen("- Set clock -", Screen::SetClock),
+    MenuItem::screen("- Time zone -", Screen::TimeZone),
+    MenuItem::submenu("-- Alarms --", ALARMS_MENU),
+    MenuItem::screen("--- Timer ---", Screen::Countdown),
+    MenuItem::screen("- Stopwatch -", Screen::Stopwatch),
+    MenuItem::screen("- Diagnostic -", Screen::Diagnostics),
+];
+
+/// one item for each of the `alarm::MAX_ALARMS`
+static ALARMS_MENU: &[MenuItem] = &[
+    MenuItem::screen("- Alarm 1 -", Screen::SetAlarm(0)),
+    MenuItem::screen("- Alarm 2 -", Screen::SetAlarm(1)),
+    MenuItem::back("- Back -"),
+];
+
+/// the normal text and the text with background(for the selected items)
+pub(crate) fn text_styles() -> (
+    MonoTextStyle<'static, BinaryColor>,
+    MonoTextStyle<'static, BinaryColor>,
+) {
+    // normal text
+    let normal = MonoTextStyleBuilder::new()
+        .font(&FONT_9X15)
+        .text_color(BinaryColor::On)
+        .build();
+    // text with background
+    let background = MonoTextStyleBuilder::from(&normal)
+        .background_color(BinaryColor::On)
+        .text_color(BinaryColor::Off)
+        .build();
+    (normal, background)
+}
+
+/// This is the principal function that renders all the menu states
+pub fn draw_menu<D>(target: &mut D, state: MenuState, msg: Option<&str>) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let logo_image = ImageRawLE::new(include_bytes!("../Images/rust.raw"), 64);
+    let (normal, background) = text_styles();
+
+    match (state, msg) {
+        (MenuState::Menu(cursor), _) => {
+            draw_list(target, &cursor, normal, background)?;
+        }
+        (MenuState::Screen(Screen::Image), Some(message)) => {
+            // Image::new(&logo_image, Point::new(32, 0)).draw(target)?;
+            Text::new(message, Point::new(0, 13), normal).draw(target)?;
+        }
+        (MenuState::Screen(Screen::Image), None) => {
+            Image::new(&logo_image, Point::new(32, 0)).draw(target)?;
+            // Text::new(message, Point::new(0, 13), normal).draw(target)?;
+        }
+        // NOTE: the screens with input have its own state and are drawn from the `screens`
+        (MenuState::Screen(_), _) => {}
+    }
+    Ok(())
+}
+
+/// Draw the visible rows of a menu and a scrollbar when not all the items fit in the display
+fn draw_list<D>(
+    target: &mut D,
+    cursor: &MenuCursor,
+    normal: MonoTextStyle<'static, BinaryColor>,
+    background: MonoTextStyle<'static, BinaryColor>,
+) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let visible = cursor
+        .items
+        .iter()
+        .enumerate()
+        .skip(cursor.first)
+        .take(VISIBLE_ROWS);
+    for (position, (row, item)) in visible.enumerate() {
+        let style = if cursor.highlight && row == cursor.selected {
+            background
+        } else {
+            normal
+        };
+        let y = FIRST_ROW_BASELINE + ROW_HEIGHT * position as i32;
+        Text::new(item.label, Point::new(0, y), style).draw(target)?;
+    }
+
+    let len = cursor.items.len();
+    if len > VISIBLE_ROWS {
+        let x = DISPLAY_WIDTH - SCROLLBAR_WIDTH as i32;
+        // the track is a dotted line and the thumb is proportional to the visible part
+        for y in (0..DISPLAY_HEIGHT).step_by(4) {
+            Pixel(Point::new(DISPLAY_WIDTH - 1, y), BinaryColor::On).draw(target)?;
+        }
+        let thumb_top = DISPLAY_HEIGHT * cursor.first as i32 / len as i32;
+        let thumb_height = (DISPLAY_HEIGHT * VISIBLE_ROWS as i32 / len as i32).max(1);
+        Rectangle::new(
+            Point::new(x, thumb_top),
+            Size::new(SCROLLBAR_WIDTH, thumb_height as u32),
+        )
+        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+        .draw(target)?;
+        // a small separator between the thumb and the rows
+        Line::new(Point::new(x - 1, 0), Point::new(x - 1, DISPLAY_HEIGHT - 1))
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::Off, 1))
+            .draw(target)?;
+    }
+    Ok(())
+}
+
+//-------------------------------------------------------------------------
+//                        finite state machine for the menu
+//-------------------------------------------------------------------------
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Key {
+    Up,
+    Down,
+    Enter,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Msg {
+    Up,    // Up button
+    Down,  // Down button
+    Enter, // Enter button
+    LongPress(Key),
+    DoubleClick(Key),
+    Repeat(Key),
+    /// redraw the screens that change with the time, every second of the RTC and faster with a
+    /// running timer
+    Tick,
+    /// an alarm started to ring
+    Alarm,
+}
+
+impl Msg {
+    /// the message of a gesture recognized in the button `key`
+    pub fn from_gesture(key: Key, gesture: Gesture) -> Self {
+        match (gesture, key) {
+            (Gesture::Click, Key::Up) => Msg::Up,
+            (Gesture::Click, Key::Down) => Msg::Down,
+            (Gesture::Click, Key::Enter) => Msg::Enter,
+            (Gesture::DoubleClick, key) => Msg::DoubleClick(key),
+            (Gesture::LongPress, key) => Msg::LongPress(key),
+            (Gesture::Repeat, key) => Msg::Repeat(key),
+        }
+    }
+}
+
+type BackgroundFlag = bool;
+
+/// The position inside a menu, the `selected` row is only highlighted after the first button
+/// press and `first` is the top row of the visible window of `VISIBLE_ROWS` rows
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MenuCursor {
+    pub items: &'static [MenuItem],
+    pub selected: usize,
+    pub highlight: BackgroundFlag,
+    pub first: usize,
+}
+
+impl MenuCursor {
+    /// the first row of a menu without any highlight
+    pub fn new(items: &'static [MenuItem]) -> Self {
+        Self {
+            items,
+            selected: 0,
+            highlight: false,
+            first: 0,
+        }
+    }
+
+    /// the item under the cursor
+    pub fn item(&self) -> Option<&'static MenuItem> {
+        self.items.get(self.selected)
+    }
+
+    /// move the visible window the minimum needed to show the selected row
+    fn scroll(self) -> Self {
+        let first = if self.selected < self.first {
+            self.selected
+        } else if self.selected >= self.first + VISIBLE_ROWS {
+            self.selected + 1 - VISIBLE_ROWS
+        } else {
+            self.first
+        };
+        Self { first, ..self }
+    }
+
+    fn up(self) -> Self {
+        let selected = if self.highlight {
+            (self.selected + self.items.len() - 1) % self.items.len()
+        } else {
+            self.selected
+        };
+        Self {
+            selected,
+            highlight: true,
+            ..self
+        }
+        .scroll()
+    }
+
+    fn down(self) -> Self {
+        let selected = if self.highlight {
+            (self.selected + 1) % self.items.len()
+        } else {
+            self.selected
+        };
+        Self {
+            selected,
+            highlight: true,
+            ..self
+        }
+        .scroll()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MenuState {
+    Menu(MenuCursor),
+    Screen(Screen),
+}
+
+impl MenuState {
+    /// the first row of a menu without any highlight
+    pub fn menu(items: &'static [MenuItem]) -> Self {
+        Self::Menu(MenuCursor::new(items))
+    }
+
+    /// check if the state is a menu
+    pub fn is_menu(&self) -> bool {
+        matches!(self, Self::Menu(_))
+    }
+}
+
+/// The menu state machine, the parents of the current state are saved in a stack so going back
+/// restores the previous selection
+#[derive(Clone)]
+pub struct MenuFSM {
+    pub state: MenuState,
+    parents: Vec<MenuCursor, MAX_DEPTH>,
+}
+
+impl MenuFSM {
+    pub fn init(state: MenuState) -> Self {
+        Self {
+            state,
+            parents: Vec::new(),
+        }
+    }
+
+    /// how many levels below the main menu is the current state
+    pub fn depth(&self) -> usize {
+        self.parents.len()
+    }
+
+    /// return to the parent menu, in the main menu this does nothing
+    pub fn back(&mut self) {
+        if let Some(parent) = self.parents.pop() {
+            self.state = MenuState::Menu(parent);
+        }
+    }
+
+    /// open the `next` state from the `cursor`, if the stack is full we stay in the same place
+    fn open(&mut self, cursor: MenuCursor, next: MenuState) {
+        if self.parents.push(cursor).is_ok() {
+            self.state = next;
+        }
+    }
+
+    /// show a `screen` that was not opened from the menu(e.g. a ringing alarm), going back
+    /// returns to the last menu
+    pub fn show(&mut self, screen: Screen) {
+        if let MenuState::Menu(cursor) = self.state {
+            self.parents.push(cursor).ok();
+        }
+        self.state = MenuState::Screen(screen);
+    }
+
+    /// a long press in Enter always goes back, and holding Up or Down scrolls fast. The screens
+    /// that handle the input receive the messages from the application
+    pub fn next_state(&mut self, msg: Msg) {
+        use MenuState::*;
+        use Msg::*;
+
+        let key = match msg {
+            Up | LongPress(Key::Up) | Repeat(Key::Up) => Key::Up,
+            Down | LongPress(Key::Down) | Repeat(Key::Down) => Key::Down,
+            Enter => Key::Enter,
+            LongPress(Key::Enter) => return self.back(),
+            Alarm => return self.show(crate::ui::Screen::Alarm),
+            Repeat(Key::Enter) | DoubleClick(_) | Tick => return,
+        };
+        match (self.state, key) {
+            (Menu(cursor), _) if cursor.items.is_empty() => {}
+            (Menu(cursor), Key::Up) => self.state = Menu(cursor.up()),
+            (Menu(cursor), Key::Down) => self.state = Menu(cursor.down()),
+            (Menu(cursor), Key::Enter) => match cursor.item().map(|item| item.kind) {
+                Some(MenuKind::Submenu(children)) => self.open(
+                    cursor,
+                    Menu(MenuCursor {
+                        highlight: true,
+                        ..MenuCursor::new(children)
+                    }),
+                ),
+                Some(MenuKind::Screen(screen)) => self.open(cursor, Screen(screen)),
+                Some(MenuKind::Back) => self.back(),
+                None => {}
+            },
+            (Screen(screen), Key::Enter) if !screen.handles_input() => self.back(),
+            (Screen(_), _) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    static SETTINGS: &[MenuItem] = &[
+        MenuItem::screen("a", Screen::Image),
+        MenuItem::screen("b", Screen::Image),
+        MenuItem::back("back"),
+    ];
+    static TREE: &[MenuItem] = &[
+        MenuItem::screen("one", Screen::Image),
+        MenuItem::screen("two", Screen::Image),
+        MenuItem::screen("three", Screen::Image),
+        MenuItem::submenu("settings", SETTINGS),
+    ];
+
+    fn selected(fsm: &MenuFSM) -> Option<(&'static str, bool)> {
+        match fsm.state {
+            MenuState::Menu(cursor) => Some((cursor.item()?.label, cursor.highlight)),
+            MenuState::Screen(_) => None,
+        }
+    }
+
+    #[test]
+    fn test_navigation() {
+        let mut fsm = MenuFSM::init(MenuState::menu(TREE));
+        assert_eq!(selected(&fsm), Some(("one", false)));
+        fsm.next_state(Msg::Down);
+        assert_eq!(selected(&fsm), Some(("one", true)));
+        fsm.next_state(Msg::Up);
+        assert_eq!(selected(&fsm), Some(("settings", true)));
+        fsm.next_state(Msg::Down);
+        assert_eq!(selected(&fsm), Some(("one", true)));
+        for _ in 0..3 {
+            fsm.next_state(Msg::Down);
+        }
+        assert_eq!(selected(&fsm), Some(("settings", true)));
+        fsm.next_state(Msg::Enter);
+        assert_eq!(selected(&fsm), Some(("a", true)));
+        fsm.next_state(Msg::Down);
+        fsm.next_state(Msg::Enter);
+        assert_eq!(fsm.state, MenuState::Screen(Screen::Image));
+    }
+
+    #[test]
+    fn test_back() {
+        let mut fsm = MenuFSM::init(MenuState::menu(TREE));
+        fsm.next_state(Msg::Down);
+        fsm.next_state(Msg::Down);
+        fsm.next_state(Msg::Enter);
+        assert_eq!(fsm.state, MenuState::Screen(Screen::Image));
+        assert_eq!(fsm.depth(), 1);
+        // the screen stays open with the arrows
+        fsm.next_state(Msg::Up);
+        assert_eq!(fsm.state, MenuState::Screen(Screen::Image));
+        // and Enter returns with the previous selection
+        fsm.next_state(Msg::Enter);
+        assert_eq!(selected(&fsm), Some(("two", true)));
+        assert_eq!(fsm.depth(), 0);
+
+        fsm.next_state(Msg::Up);
+        fsm.next_state(Msg::Up);
+        fsm.next_state(Msg::Enter);
+        assert_eq!(selected(&fsm), Some(("a", true)));
+        fsm.next_state(Msg::Enter);
+        fsm.next_state(Msg::Enter);
+        assert_eq!(selected(&fsm), Some(("a", true)));
+        fsm.next_state(Msg::Up);
+        assert_eq!(selected(&fsm), Some(("back", true)));
+        fsm.next_state(Msg::Enter);
+        assert_eq!(selected(&fsm), Some(("settings", true)));
+        assert_eq!(fsm.depth(), 0);
+        // in the main menu going back does nothing
+        fsm.back();
+        assert_eq!(selected(&fsm), Some(("settings", true)));
+    }
+
+    #[test]
+    fn test_screen_with_input() {
+        static MENU: &[MenuItem] = &[MenuItem::screen("clock", Screen::SetClock)];
+        let mut fsm = MenuFSM::init(MenuState::menu(MENU));
+        fsm.next_state(Msg::Enter);
+        fsm.next_state(Msg::Enter);
+        fsm.next_state(Msg::Down);
+        assert_eq!(fsm.state, MenuState::Screen(Screen::SetClock));
+        fsm.next_state(Msg::LongPress(Key::Enter));
+        assert_eq!(selected(&fsm), Some(("clock", false)));
+    }
+
+    #[test]
+    fn test_alarm() {
+        let mut fsm = MenuFSM::init(MenuState::menu(TREE));
+        fsm.next_state(Msg::Down);
+        fsm.next_state(Msg::Alarm);
+        assert_eq!(fsm.state, MenuState::Screen(Screen::Alarm));
+        // the buttons are for the alarm screen
+        fsm.next_state(Msg::Enter);
+        assert_eq!(fsm.state, MenuState::Screen(Screen::Alarm));
+        fsm.back();
+        assert_eq!(selected(&fsm), Some(("one", true)));
+        // over other screen it replaces the screen
+        fsm.next_state(Msg::Enter);
+        fsm.next_state(Msg::Alarm);
+        assert_eq!(fsm.depth(), 1);
+        fsm.back();
+        assert_eq!(selected(&fsm), Some(("one", true)));
+    }
+
+    #[test]
+    fn test_gestures() {
+        let mut fsm = MenuFSM::init(MenuState::menu(TREE));
+        fsm.next_state(Msg::LongPress(Key::Up));
+        fsm.next_state(Msg::Repeat(Key::Up));
+        fsm.next_state(Msg::Repeat(Key::Up));
+        assert_eq!(selected(&fsm), Some(("three", true)));
+        fsm.next_state(Msg::Repeat(Key::Down));
+        fsm.next_state(Msg::Enter);
+        assert_eq!(selected(&fsm), Some(("a", true)));
+        fsm.next_state(Msg::DoubleClick(Key::Enter));
+        fsm.next_state(Msg::Tick);
+        assert_eq!(selected(&fsm), Some(("a", true)));
+        fsm.next_state(Msg::LongPress(Key::Enter));
+        assert_eq!(selected(&fsm), Some(("settings", true)));
+    }
+
+    #[test]
+    fn test_scroll() {
+        let first = |fsm: &MenuFSM| match fsm.state {
+            MenuState::Menu(cursor) => cursor.first,
+            MenuState::Screen(_) => unreachable!(),
+        };
+        let mut fsm = MenuFSM::init(MenuState::menu(TREE));
+        fsm.next_state(Msg::Down);
+        fsm.next_state(Msg::Down);
+        fsm.next_state(Msg::Down);
+        assert_eq!(selected(&fsm), Some(("three", true)));
+        assert_eq!(first(&fsm), 0);
+        fsm.next_state(Msg::Down);
+        assert_eq!(selected(&fsm), Some(("settings", true)));
+        assert_eq!(first(&fsm), 1);
+        fsm.next_state(Msg::Up);
+        fsm.next_state(Msg::Up);
+        assert_eq!(first(&fsm), 1);
+        fsm.next_state(Msg::Up);
+        assert_eq!(selected(&fsm), Some(("one", true)));
+        assert_eq!(first(&fsm), 0);
+        // wrap around to the end of the list
+        fsm.next_state(Msg::Up);
+        assert_eq!(first(&fsm), 1);
+    }
+}
@@ -0,0 +1,31 @@
+/// The images of the frames drawn in the `Canvas`, the same memory copy of the screen that the
+/// firmware sends to the SH1106
+use std::io::{self, Write};
+
+use rtic_oled_ui_core::canvas::ROW_BYTES;
+use rtic_oled_ui_core::ui::{DISPLAY_HEIGHT, DISPLAY_WIDTH};
+use rtic_oled_ui_core::Canvas;
+
+/// write the frame as a binary PBM(P4) image, the lit pixels are white like in the OLED
+pub fn write_pbm<W: Write>(canvas: &Canvas, mut out: W) -> io::Result<()> {
+    write!(out, "P4\n{} {}\n", DISPLAY_WIDTH, DISPLAY_HEIGHT)?;
+    // NOTE: the rows of the canvas are already packed like the PBM but there a one is black
+    for row in canvas.rows() {
+        let inverted: [u8; ROW_BYTES] = core::array::from_fn(|i| !row[i]);
+        out.write_all(&inverted)?;
+    }
+    Ok(())
+}
+
+/// write the frame as text, useful to see the screen directly in the terminal
+pub fn write_ascii<W: Write>(canvas: &Canvas, mut out: W) -> io::Result<()> {
+    let width = DISPLAY_WIDTH as usize;
+    writeln!(out, "+{}+", "-".repeat(width))?;
+    for y in 0..DISPLAY_HEIGHT as usize {
+        let line: String = (0..width)
+            .map(|x| if canvas.is_on(x, y) { '#' } else { ' ' })
+            .collect();
+        writeln!(out, "|{}|", line)?;
+    }
+    writeln!(out, "+{}+", "-".repeat(width))
+}
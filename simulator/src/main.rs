@@ -0,0 +1,202 @@
+//----------------------------------------------------------------------------
+// Host side simulator for the menu UI
+//
+// It renders the same screens that run on the Blue Pill into the 128x64
+// `Canvas` of the firmware and drives the same `AppFSM` with events readed from the keyboard
+// (stdin) or from a script file, every frame is saved as a PBM image.
+//
+// usage: rtic-oled-ui-simulator [--out DIR] [--ascii] [SCRIPT]
+//
+// events (one per line, `#` starts a comment), a button optionally preceded by a gesture:
+//  - buttons: `u` | `up`, `d` | `down`, `e` | `enter`
+//  - gestures: `click`(the default), `double`, `long`, `repeat`
+//  - `t` | `tick`: one second of the RTC
+//  - `a` | `alarm`: the RTC alarm interrupt
+//  - `w MS` | `wait MS`: advance the monotonic clock of the timers and redraw
+//----------------------------------------------------------------------------
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter};
+use std::path::PathBuf;
+
+mod frame;
+mod rtc;
+
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+use rtc::Rtc;
+use rtic_oled_ui_core::alarm::MAX_ALARMS;
+use rtic_oled_ui_core::timers::Millis;
+use rtic_oled_ui_core::{
+    Alarm, AppFSM, Canvas, DateTime, Diagnostics, Effect, Gesture, Key, Msg, TimeZone,
+};
+
+/// The lines of a script
+#[derive(Debug, PartialEq)]
+enum Event {
+    Msg(Msg),
+    /// the time passes in the monotonic clock(the `Systick` of the firmware)
+    Wait(Millis),
+}
+
+/// parse a line of the script in a `Event`, empty lines and comments are `None`
+fn parse_event(line: &str) -> Result<Option<Event>, String> {
+    let event = line.split('#').next().unwrap_or("").trim().to_lowercase();
+    let mut words = event.split_whitespace();
+    match (words.next(), words.next()) {
+        (Some("t" | "tick"), None) => return Ok(Some(Event::Msg(Msg::Tick))),
+        (Some("a" | "alarm"), None) => return Ok(Some(Event::Msg(Msg::Alarm))),
+        (Some("w" | "wait"), Some(ms)) => {
+            let ms = ms.parse().map_err(|_| format!("invalid time: `{}`", ms))?;
+            return Ok(Some(Event::Wait(ms)));
+        }
+        _ => {}
+    }
+    let mut words = event.split_whitespace();
+    let (gesture, key) = match (words.next(), words.next()) {
+        (None, _) => return Ok(None),
+        (Some(key), None) => ("click", key),
+        (Some(gesture), Some(key)) => (gesture, key),
+    };
+    let key = match key {
+        "u" | "up" => Key::Up,
+        "d" | "down" => Key::Down,
+        "e" | "enter" => Key::Enter,
+        other => return Err(format!("unknown button: `{}`", other)),
+    };
+    let gesture = match gesture {
+        "click" => Gesture::Click,
+        "double" => Gesture::DoubleClick,
+        "long" => Gesture::LongPress,
+        "repeat" => Gesture::Repeat,
+        other => return Err(format!("unknown gesture: `{}`", other)),
+    };
+    Ok(Some(Event::Msg(Msg::from_gesture(key, gesture))))
+}
+
+struct Simulator {
+    canvas: Canvas,
+    app_fsm: AppFSM,
+    millis: Millis,
+    rtc: Rtc,
+    out_dir: PathBuf,
+    ascii: bool,
+    frame: usize,
+}
+
+impl Simulator {
+    fn new(out_dir: PathBuf, ascii: bool) -> Self {
+        let rtc = Rtc::new();
+        Self {
+            canvas: Canvas::new(),
+            app_fsm: AppFSM::new(
+                DateTime::new(rtc.current_time()),
+                TimeZone::UTC,
+                [Alarm::default(); MAX_ALARMS],
+            ),
+            millis: 0,
+            rtc,
+            out_dir,
+            ascii,
+            frame: 0,
+        }
+    }
+
+    /// the same steps that the `dispatch_msg` task does in the firmware
+    fn dispatch_msg(&mut self, msg: Msg) -> io::Result<()> {
+        self.app_fsm.poll(self.millis);
+        if msg == Msg::Tick && !self.app_fsm.is_animated() {
+            return Ok(());
+        }
+        let now = self.app_fsm.timezone.to_local(self.rtc.current_time());
+        self.canvas.clear(BinaryColor::Off).ok();
+        // there is no RTC alarm to stop or snooze and no led, only the clock is set
+        if let Some(Effect::SetTime(utc)) = self.app_fsm.update(msg, &now, self.millis) {
+            self.rtc.set_time(utc);
+        }
+        // NOTE: the simulator never panics
+        self.app_fsm
+            .draw(
+                &mut self.canvas,
+                &now,
+                self.millis,
+                &Diagnostics::default(),
+                msg,
+            )
+            .ok();
+        self.flush()
+    }
+
+    /// save the current frame
+    fn flush(&mut self) -> io::Result<()> {
+        let path = self.out_dir.join(format!("frame_{:04}.pbm", self.frame));
+        frame::write_pbm(&self.canvas, BufWriter::new(File::create(&path)?))?;
+        if self.ascii {
+            frame::write_ascii(&self.canvas, io::stdout().lock())?;
+        }
+        println!("{}", path.display());
+        self.frame += 1;
+        Ok(())
+    }
+}
+
+fn main() -> io::Result<()> {
+    let mut out_dir = PathBuf::from("frames");
+    let mut ascii = false;
+    let mut script = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => {
+                out_dir = args.next().map(PathBuf::from).unwrap_or(out_dir);
+            }
+            "--ascii" => ascii = true,
+            _ => script = Some(PathBuf::from(arg)),
+        }
+    }
+    fs::create_dir_all(&out_dir)?;
+
+    let input: Box<dyn BufRead> = match script {
+        Some(path) => Box::new(BufReader::new(File::open(path)?)),
+        None => Box::new(BufReader::new(io::stdin())),
+    };
+
+    let mut simulator = Simulator::new(out_dir, ascii);
+    // the first frame is the blank screen after `display.init()`
+    simulator.flush()?;
+    for (number, line) in input.lines().enumerate() {
+        match parse_event(&line?) {
+            Ok(Some(Event::Msg(msg))) => simulator.dispatch_msg(msg)?,
+            Ok(Some(Event::Wait(ms))) => {
+                simulator.millis = simulator.millis.wrapping_add(ms);
+                simulator.dispatch_msg(Msg::Tick)?;
+            }
+            Ok(None) => {}
+            Err(err) => eprintln!("line {}: {}", number + 1, err),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_event() {
+        let msg = |line| match parse_event(line) {
+            Ok(Some(Event::Msg(msg))) => Some(msg),
+            _ => None,
+        };
+        assert_eq!(msg("up"), Some(Msg::Up));
+        assert_eq!(msg(" D "), Some(Msg::Down));
+        assert_eq!(msg("e # select"), Some(Msg::Enter));
+        assert_eq!(parse_event("# comment"), Ok(None));
+        assert_eq!(msg("long enter"), Some(Msg::LongPress(Key::Enter)));
+        assert_eq!(msg("repeat u"), Some(Msg::Repeat(Key::Up)));
+        assert_eq!(msg("tick"), Some(Msg::Tick));
+        assert_eq!(msg("a"), Some(Msg::Alarm));
+        assert_eq!(parse_event("wait 1500"), Ok(Some(Event::Wait(1500))));
+        assert!(parse_event("w soon").is_err());
+        assert!(parse_event("left").is_err());
+        assert!(parse_event("triple up").is_err());
+    }
+}
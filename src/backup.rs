@@ -0,0 +1,112 @@
+/// Values that survive the resets in the backup data registers(DR1..DR10 in the medium density
+/// devices), they are only lost when VBAT and VDD are off, the same as the RTC counter
+use rtic_oled_ui_core::alarm::MAX_ALARMS;
+use rtic_oled_ui_core::{Alarm, PanicRecord, TimeZone};
+use stm32f1xx_hal::backup_domain::BackupDomain;
+use stm32f1xx_hal::pac;
+
+/// index of the data registers used by the application
+#[derive(Copy, Clone)]
+enum Register {
+    /// `RTC_MAGIC` when the RTC counter has a valid time
+    RtcMagic = 0,
+    /// `TimeZone::to_bits`, the zero of the first power up is UTC
+    TimeZone = 1,
+    /// the first of the two registers of each alarm with `Alarm::to_bits`
+    Alarms = 2,
+    /// the first of the four registers of `PanicRecord::to_bits`
+    Panic = 6,
+}
+
+// the values don't overlap and fit in the 10 data registers
+const _: () = assert!(Register::Alarms as usize + 2 * MAX_ALARMS <= Register::Panic as usize);
+const _: () = assert!(Register::Panic as usize + 4 <= 10);
+
+const RTC_MAGIC: u16 = 0x5254;
+
+pub struct Backup {
+    domain: BackupDomain,
+}
+
+impl Backup {
+    pub fn new(domain: BackupDomain) -> Self {
+        Self { domain }
+    }
+
+    fn read(&self, register: Register) -> u16 {
+        self.read_at(register, 0)
+    }
+
+    fn write(&mut self, register: Register, value: u16) {
+        self.write_at(register, 0, value)
+    }
+
+    /// the data register `offset` places after `register`, for the values with many registers
+    fn read_at(&self, register: Register, offset: usize) -> u16 {
+        self.domain
+            .read_data_register_low(register as usize + offset)
+    }
+
+    fn write_at(&mut self, register: Register, offset: usize, value: u16) {
+        self.domain
+            .write_data_register_low(register as usize + offset, value)
+    }
+
+    /// check if the RTC was seeded in a previous power up
+    pub fn is_rtc_initialized(&self) -> bool {
+        self.read(Register::RtcMagic) == RTC_MAGIC
+    }
+
+    pub fn set_rtc_initialized(&mut self) {
+        self.write(Register::RtcMagic, RTC_MAGIC)
+    }
+
+    pub fn timezone(&self) -> TimeZone {
+        TimeZone::from_bits(self.read(Register::TimeZone))
+    }
+
+    pub fn set_timezone(&mut self, timezone: TimeZone) {
+        self.write(Register::TimeZone, timezone.to_bits())
+    }
+
+    pub fn alarm(&self, index: usize) -> Alarm {
+        Alarm::from_bits([
+            self.read_at(Register::Alarms, 2 * index),
+            self.read_at(Register::Alarms, 2 * index + 1),
+        ])
+    }
+
+    pub fn set_alarm(&mut self, index: usize, alarm: Alarm) {
+        let [time, flags] = alarm.to_bits();
+        self.write_at(Register::Alarms, 2 * index, time);
+        self.write_at(Register::Alarms, 2 * index + 1, flags);
+    }
+
+    /// the last panic, it is kept in the next boots until other panic replaces it
+    pub fn panic(&self) -> Option<PanicRecord> {
+        PanicRecord::from_bits(core::array::from_fn(|offset| {
+            self.read_at(Register::Panic, offset)
+        }))
+    }
+}
+
+/// save a panic in the registers without the `Backup`, it is owned by a task that can't run
+/// anymore. Returns the record with the count of the previous panics
+#[allow(unsafe_code)]
+pub fn save_panic(file: &str, line: u32) -> PanicRecord {
+    // SAFETY: the panic handler doesn't return so nothing else uses these registers
+    let (rcc, pwr, bkp) = unsafe { (&*pac::RCC::ptr(), &*pac::PWR::ptr(), &*pac::BKP::ptr()) };
+    // the same as the `constrain` of the `init`, the panic can be before it
+    rcc.apb1enr
+        .modify(|_, w| w.pwren().set_bit().bkpen().set_bit());
+    pwr.cr.modify(|_, w| w.dbp().set_bit());
+    let registers = &bkp.dr[Register::Panic as usize..Register::Panic as usize + 4];
+    let previous = PanicRecord::from_bits(core::array::from_fn(|offset| {
+        registers[offset].read().d().bits()
+    }));
+    let record = PanicRecord::new(file, line, previous);
+    for (register, bits) in registers.iter().zip(record.to_bits()) {
+        register.write(|w| w.d().bits(bits));
+    }
+    record
+}
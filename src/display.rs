@@ -0,0 +1,151 @@
+/// The SH1106 display in the I2C1, the tasks and the panic handler connect it the same way
+use embedded_hal::blocking::i2c::Write;
+use rtic_oled_ui_core::Canvas;
+use sh1106::{prelude::*, Builder};
+use stm32f1xx_hal::{
+    afio::MAPR,
+    gpio,
+    i2c::{self, BlockingI2c, DutyCycle, Mode},
+    pac::{GPIOB, I2C1},
+    rcc::Clocks,
+    time::Hertz,
+};
+
+pub type Scl = gpio::gpiob::PB8<gpio::Alternate<gpio::OpenDrain>>;
+pub type Sda = gpio::gpiob::PB9<gpio::Alternate<gpio::OpenDrain>>;
+pub type OledDisplay = GraphicsMode<I2cInterface<I2cBus>>;
+
+/// the clock of the I2C bus
+const FREQUENCY: Hertz = Hertz::kHz(100);
+/// the contrast that `init` sets, writing it again checks that the display answers
+const CONTRAST: u8 = 0x80;
+/// the clock pulses that finish any byte that a device is sending
+const RECOVERY_PULSES: usize = 9;
+
+/// The I2C1 of the display, a failed transfer recovers the bus so a locked state doesn't fail
+/// the next transfers
+pub struct I2cBus {
+    i2c: BlockingI2c<I2C1, (Scl, Sda)>,
+    /// half of a period of the clock of the bus in cycles of the core
+    half_period: u32,
+}
+
+impl I2cBus {
+    /// free a bus that a device holds(SDA low in the middle of a byte, e.g. the display was
+    /// unplugged in a transfer): the clock pulses let it finish the byte and a STOP ends the
+    /// transfer. Then the I2C1 is reset
+    #[allow(unsafe_code)]
+    fn recover(&mut self) {
+        // SAFETY: the pins and the registers belong to the `BlockingI2c` of this bus and it
+        // isn't in a transfer
+        let (gpiob, i2c) = unsafe { (&*GPIOB::ptr(), &*I2C1::ptr()) };
+        let delay = || cortex_m::asm::delay(self.half_period);
+        i2c.cr1.modify(|_, w| w.pe().clear_bit());
+        // PB8(SCL) and PB9(SDA) are open drain outputs(CNF 01, MODE 10) during the recovery
+        let crh = gpiob.crh.read().bits();
+        gpiob.bsrr.write(|w| w.bs8().set_bit().bs9().set_bit());
+        // SAFETY: only the bits of PB8 and PB9 change
+        unsafe { gpiob.crh.write(|w| w.bits(crh & !0xff | 0x66)) };
+        for _ in 0..RECOVERY_PULSES {
+            if gpiob.idr.read().idr9().bit_is_set() {
+                break;
+            }
+            gpiob.bsrr.write(|w| w.br8().set_bit());
+            delay();
+            gpiob.bsrr.write(|w| w.bs8().set_bit());
+            delay();
+        }
+        // the STOP is a rising SDA while SCL is high
+        gpiob.bsrr.write(|w| w.br8().set_bit());
+        delay();
+        gpiob.bsrr.write(|w| w.br9().set_bit());
+        delay();
+        gpiob.bsrr.write(|w| w.bs8().set_bit());
+        delay();
+        gpiob.bsrr.write(|w| w.bs9().set_bit());
+        delay();
+        // SAFETY: the same configuration of the pins
+        unsafe { gpiob.crh.write(|w| w.bits(crh)) };
+        self.reset();
+    }
+
+    /// the software reset of the I2C1, the `BlockingI2c` can't be released to create it again
+    /// so its configuration is restored like in `I2c::i2c1`
+    #[allow(unsafe_code)]
+    fn reset(&mut self) {
+        // SAFETY: the registers belong to the `BlockingI2c` of this bus and it isn't in a transfer
+        let i2c = unsafe { &*I2C1::ptr() };
+        let (cr2, ccr, trise) = (
+            i2c.cr2.read().bits(),
+            i2c.ccr.read().bits(),
+            i2c.trise.read().bits(),
+        );
+        i2c.cr1.write(|w| w.swrst().set_bit());
+        i2c.cr1.reset();
+        // SAFETY: the values were read from the same registers
+        unsafe {
+            i2c.cr2.write(|w| w.bits(cr2));
+            i2c.ccr.write(|w| w.bits(ccr));
+            i2c.trise.write(|w| w.bits(trise));
+        }
+        i2c.cr1.write(|w| w.pe().set_bit());
+    }
+}
+
+impl Write for I2cBus {
+    type Error = i2c::Error;
+
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), i2c::Error> {
+        let result = self.i2c.write(address, bytes);
+        match result {
+            // a missing display doesn't leave the bus in a bad state
+            Ok(()) | Err(i2c::Error::Acknowledge) => {}
+            Err(_) => self.recover(),
+        }
+        result
+    }
+}
+
+/// start the display and clear it, the errors are ignored so the firmware runs without display.
+/// The timeouts of the bus need the DWT cycle counter
+pub fn connect(i2c: I2C1, pins: (Scl, Sda), mapr: &mut MAPR, clocks: Clocks) -> OledDisplay {
+    let i2c = BlockingI2c::i2c1(
+        i2c,
+        pins,
+        mapr,
+        Mode::Fast {
+            frequency: FREQUENCY,
+            duty_cycle: DutyCycle::Ratio2to1,
+        },
+        clocks,
+        1000,
+        10,
+        1000,
+        1000,
+    );
+    let bus = I2cBus {
+        i2c,
+        half_period: clocks.sysclk().raw() / FREQUENCY.raw() / 2,
+    };
+    let mut display: GraphicsMode<_> = Builder::new().connect_i2c(bus).into();
+    display.init().ok();
+    display.flush().ok();
+    display
+}
+
+/// copy a frame in the buffer of the display driver, the `flush` of the display shows it
+pub fn copy(display: &mut OledDisplay, canvas: &Canvas) {
+    for (y, row) in canvas.rows().enumerate() {
+        for (x, byte) in row.iter().enumerate() {
+            for bit in 0..8 {
+                let on = byte & (0x80 >> bit) != 0;
+                display.set_pixel((x * 8 + bit) as u32, y as u32, u8::from(on));
+            }
+        }
+    }
+}
+
+/// check that the display answers in the I2C bus without changing what it shows
+pub fn is_present(display: &mut OledDisplay) -> bool {
+    display.set_contrast(CONTRAST).is_ok()
+}
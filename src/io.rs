@@ -1,47 +1,122 @@
 // use embedded_hal as hal;
 
 // use hal::serial::Write;
-use nb::block;
-use stm32f1xx_hal::pac::USART1;
+use core::convert::Infallible;
 
-use stm32f1xx_hal::serial::Tx;
+use heapless::spsc::Producer;
+use rtic_oled_ui_core::log;
+#[cfg(not(feature = "rtt"))]
+use stm32f1xx_hal::pac::Interrupt;
+use stm32f1xx_hal::serial;
 
-pub struct Logger {
-    tx_pin: Tx<USART1>,
+/// the bytes of the log that wait to be sent by the USART1 interrupt, 1 second at 9600 bauds
+pub const QUEUE_LEN: usize = 1024;
+
+/// the UART logger, the lines are queued and the USART1 interrupt sends them so the tasks don't
+/// wait for the slow UART
+#[cfg(not(feature = "rtt"))]
+pub type Logger = log::Logger<LogQueue>;
+/// the RTT logger, the debug probe reads the lines from the memory
+#[cfg(feature = "rtt")]
+pub type Logger = log::Logger<RttPort>;
+
+/// the serial port of the log, the `queue` of the USART1 is used only without RTT
+#[cfg(not(feature = "rtt"))]
+pub fn log_port(queue: Producer<'static, u8, QUEUE_LEN>) -> LogQueue {
+    LogQueue(queue)
 }
 
-// TODO(elsuizo:2021-11-18): RX ??? maybe???
-/// a UART logger interface
-impl Logger {
-    pub fn new(tx_pin: Tx<USART1>) -> Self {
-        Self { tx_pin }
-    }
+#[cfg(feature = "rtt")]
+pub fn log_port(_queue: Producer<'static, u8, QUEUE_LEN>) -> RttPort {
+    let channels = rtt_target::rtt_init! {
+        up: {
+            0: {
+                size: 1024
+                mode: NoBlockSkip
+                name: "Log"
+            }
+        }
+    };
+    RttPort(channels.up.0)
+}
+
+/// The serial port of the log, a queue that the USART1 interrupt sends while the UART is ready
+#[cfg(not(feature = "rtt"))]
+pub struct LogQueue(Producer<'static, u8, QUEUE_LEN>);
 
-    pub fn log(&mut self, data: &str) -> Result<(), ()> {
-        self.send("LOG: ".as_bytes())?;
-        self.send(data.as_bytes())?;
-        self.send("\r\n".as_bytes())
+// NOTE: only the tasks with less priority than the USART1 interrupt can wait for free space
+#[cfg(not(feature = "rtt"))]
+impl embedded_hal::serial::Write<u8> for LogQueue {
+    type Error = Infallible;
+
+    fn write(&mut self, byte: u8) -> nb::Result<(), Infallible> {
+        self.0.enqueue(byte).map_err(|_| {
+            rtic::pend(Interrupt::USART1);
+            nb::Error::WouldBlock
+        })
     }
 
-    pub fn warn(&mut self, data: &str) -> Result<(), ()> {
-        self.send("WRN: ".as_bytes())?;
-        self.send(data.as_bytes())?;
-        self.send("\r\n".as_bytes())
+    /// start the USART1 interrupt, it is done when the queue is empty
+    fn flush(&mut self) -> nb::Result<(), Infallible> {
+        rtic::pend(Interrupt::USART1);
+        match self.0.len() {
+            0 => Ok(()),
+            _ => Err(nb::Error::WouldBlock),
+        }
     }
+}
 
-    pub fn error(&mut self, data: &str) -> Result<(), ()> {
-        self.send("ERR: ".as_bytes())?;
-        self.send(data.as_bytes())?;
-        self.send("\r\n".as_bytes())
+// NOTE: the USART1 interrupt only takes bytes out, so the free space can only grow meanwhile
+#[cfg(not(feature = "rtt"))]
+impl log::WriteFrame for LogQueue {
+    fn write_frame(&mut self, frame: &[u8]) -> nb::Result<(), Infallible> {
+        if self.0.capacity() - self.0.len() < frame.len() {
+            rtic::pend(Interrupt::USART1);
+            return Err(nb::Error::WouldBlock);
+        }
+        for &byte in frame {
+            self.0.enqueue(byte).ok();
+        }
+        Ok(())
     }
+}
 
-    pub fn send(&mut self, buf: &[u8]) -> Result<(), ()> {
-        for &byte in buf {
-            if byte == 0x00 {
-                continue;
-            }
-            block!(self.tx_pin.write(byte)).ok();
+/// The serial port of the log in the up channel 0 of RTT, the bytes that don't fit in the
+/// channel are lost while the debug probe is not reading
+#[cfg(feature = "rtt")]
+pub struct RttPort(pub rtt_target::UpChannel);
+
+#[cfg(feature = "rtt")]
+impl embedded_hal::serial::Write<u8> for RttPort {
+    type Error = Infallible;
+
+    fn write(&mut self, byte: u8) -> nb::Result<(), Infallible> {
+        match self.0.write(&[byte]) {
+            0 => Err(nb::Error::WouldBlock),
+            _ => Ok(()),
         }
+    }
+
+    fn flush(&mut self) -> nb::Result<(), Infallible> {
         Ok(())
     }
 }
+
+// NOTE: in the `NoBlockSkip` mode of the channel a write that doesn't fit is skipped whole
+#[cfg(feature = "rtt")]
+impl log::WriteFrame for RttPort {
+    fn write_frame(&mut self, frame: &[u8]) -> nb::Result<(), Infallible> {
+        match self.0.write(frame) {
+            0 if !frame.is_empty() => Err(nb::Error::WouldBlock),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// the log error of a byte received with errors
+pub fn rx_error(err: serial::Error) -> log::Error {
+    match err {
+        serial::Error::Overrun => log::Error::Overrun,
+        _ => log::Error::Framing,
+    }
+}
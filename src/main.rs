@@ -14,31 +14,76 @@
 #![no_main]
 #![no_std]
 
-mod buttons;
-mod datetime;
+mod backup;
+mod display;
 mod io;
-mod ui;
+// NOTE: the panic handler steals the peripherals of the tasks
+#[allow(unsafe_code)]
+mod panic;
 
-use crate::buttons::Button;
+use crate::backup::Backup;
+use crate::display::OledDisplay;
 use crate::io::Logger;
-use datetime::DateTime;
-use panic_semihosting as _;
 use rtic::app;
+use rtic_oled_ui_core::alarm::{next_alarm, MAX_ALARMS, SNOOZE_SECONDS};
+use rtic_oled_ui_core::buttons::POLL_PERIOD_MS;
+use rtic_oled_ui_core::canvas::ROW_BYTES;
+use rtic_oled_ui_core::console::{ParseError, HELP};
+use rtic_oled_ui_core::log;
+use rtic_oled_ui_core::timers::Millis;
+use rtic_oled_ui_core::{error, info, warn};
+use rtic_oled_ui_core::{
+    ui, Alarm, AlarmAction, AppError, AppFSM, Button, Canvas, Command, Console, DateTime,
+    DayOfWeek, Diagnostics, Effect, ErrorCounters, Gesture, TimeZone,
+};
 use stm32f1xx_hal::gpio::PinState;
-use stm32f1xx_hal::{gpio, pac, prelude::*};
+use stm32f1xx_hal::{flash, gpio, pac, prelude::*, rcc};
 
 use core::fmt::Write;
 
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::DrawTarget};
+use heapless::spsc::{Consumer, Producer, Queue};
 use heapless::String;
-use pac::I2C1;
-use sh1106::{prelude::*, Builder};
 use stm32f1xx_hal::{
-    i2c::{BlockingI2c, DutyCycle, Mode},
     rtc::Rtc,
-    serial::{Config, Serial},
+    serial::{Config, Rx, Serial, Tx},
 };
 use systick_monotonic::{fugit::Duration, Systick};
 
+/// The inputs of the `dispatch_msg` task
+pub enum Event {
+    Msg(ui::Msg),
+    /// a line of the serial console
+    Command(Result<Command, ParseError>),
+    /// a byte of the serial console was lost
+    SerialError(log::Error),
+}
+
+/// the bytes received by the serial console, or its errors, that wait to be collected in lines
+const RX_QUEUE_LEN: usize = 64;
+
+/// the period of the redraws of a running timer, a full frame takes near 100 ms in the I2C bus
+const REFRESH_PERIOD_MS: u64 = 200;
+
+/// how many times a frame is sent again after initializing the display
+const DISPLAY_RETRIES: usize = 2;
+
+/// the sources of the ringing, each one is stopped on its own
+const RING_ALARM: u8 = 1 << 0;
+const RING_COUNTDOWN: u8 = 1 << 1;
+
+/// the clocks of the firmware, the panic handler sets them again to use the display
+pub fn clocks(cfgr: rcc::CFGR, acr: &mut flash::ACR) -> rcc::Clocks {
+    cfgr.use_hse(8.MHz())
+        .sysclk(36.MHz())
+        .pclk1(36.MHz())
+        .freeze(acr)
+    // cfgr.use_hse(8.MHz())
+    //     .sysclk(72.MHz())
+    //     .pclk1(36.MHz())
+    //     .freeze(acr)
+}
+
 #[app(device = stm32f1xx_hal::pac, peripherals = true, dispatchers = [SPI1])]
 mod app {
     use super::*;
@@ -46,12 +91,9 @@ mod app {
     //                        type alias
     //-------------------------------------------------------------------------
     type Led = gpio::gpioc::PC13<gpio::Output<gpio::PushPull>>;
-    type Sda = gpio::gpiob::PB9<gpio::Alternate<gpio::OpenDrain>>;
-    type Scl = gpio::gpiob::PB8<gpio::Alternate<gpio::OpenDrain>>;
     type ButtonUpPin = gpio::gpioa::PA5<gpio::Input<gpio::PullUp>>;
     type ButtonDownPin = gpio::gpioa::PA6<gpio::Input<gpio::PullUp>>;
     type ButtonEnterPin = gpio::gpioa::PA7<gpio::Input<gpio::PullUp>>;
-    type OledDisplay = GraphicsMode<I2cInterface<BlockingI2c<I2C1, (Scl, Sda)>>>;
 
     #[monotonic(binds = SysTick, default = true)]
     type MonoTimer = Systick<1000>;
@@ -62,6 +104,12 @@ mod app {
     #[shared]
     struct Shared {
         led: Led,
+        exti: pac::EXTI,
+        rtc: Rtc,
+        /// the `RING_*` sources that are ringing, the led blinks until all of them are stopped
+        ringing: u8,
+        /// the tasks count their errors here
+        diagnostics: Diagnostics,
     }
 
     #[local]
@@ -69,16 +117,24 @@ mod app {
         button_up: Button<ButtonUpPin>,
         button_down: Button<ButtonDownPin>,
         button_enter: Button<ButtonEnterPin>,
-        rtc: Rtc,
         display: OledDisplay,
         logger: Logger,
-        menu_fsm: crate::ui::MenuFSM,
+        app_fsm: AppFSM,
+        backup: Backup,
+        rx: Rx<pac::USART1>,
+        tx: Tx<pac::USART1>,
+        producer: Producer<'static, Result<u8, log::Error>, RX_QUEUE_LEN>,
+        consumer: Consumer<'static, Result<u8, log::Error>, RX_QUEUE_LEN>,
+        log_queue: Consumer<'static, u8, { io::QUEUE_LEN }>,
     }
 
     //-------------------------------------------------------------------------
     //                        initialization fn
     //-------------------------------------------------------------------------
-    #[init]
+    #[init(local = [
+        rx_queue: Queue<Result<u8, log::Error>, RX_QUEUE_LEN> = Queue::new(),
+        log_buffer: Queue<u8, { io::QUEUE_LEN }> = Queue::new(),
+    ])]
     fn init(cx: init::Context) -> (Shared, Local, init::Monotonics) {
         //-------------------------------------------------------------------------
         //                        hardware initialization
@@ -87,19 +143,7 @@ mod app {
         let mut pwr = cx.device.PWR;
         let mut flash = cx.device.FLASH.constrain();
         // let clocks = rcc.cfgr.freeze(&mut flash.acr);
-        let clocks = rcc
-            .cfgr
-            .use_hse(8.MHz())
-            .sysclk(36.MHz())
-            .pclk1(36.MHz())
-            .freeze(&mut flash.acr);
-
-        // let clocks = rcc
-        //     .cfgr
-        //     .use_hse(8.MHz())
-        //     .sysclk(72.MHz())
-        //     .pclk1(36.MHz())
-        //     .freeze(&mut flash.acr);
+        let clocks = clocks(rcc.cfgr, &mut flash.acr);
         let mut afio = cx.device.AFIO.constrain();
         let mut backup_domain = rcc.bkp.constrain(cx.device.BKP, &mut pwr);
 
@@ -113,47 +157,60 @@ mod app {
         // USART1
         let tx = gpiob.pb6.into_alternate_push_pull(&mut gpiob.crl);
         let rx = gpiob.pb7;
-        let serial = Serial::new(
+        let mut serial = Serial::new(
             cx.device.USART1,
             (tx, rx),
             &mut afio.mapr,
             Config::default().baudrate(9600.bps()),
             &clocks,
         );
-        let tx = serial.split().0;
-        let logger = Logger::new(tx);
+        // NOTE: the RX interrupt sends the bytes of the console to the queue
+        serial.listen(stm32f1xx_hal::serial::Event::Rxne);
+        let (tx, rx) = serial.split();
+        // NOTE: the timestamps are valid after the `init`, nothing is logged before
+        let (log_producer, log_queue) = cx.local.log_buffer.split();
+        let logger = Logger::new(io::log_port(log_producer), || {
+            monotonics::now().ticks() as Millis
+        });
+        let (producer, consumer) = cx.local.rx_queue.split();
         // oled display pins
         let scl = gpiob.pb8.into_alternate_open_drain(&mut gpiob.crh);
         let sda = gpiob.pb9.into_alternate_open_drain(&mut gpiob.crh);
-        let i2c = BlockingI2c::i2c1(
-            cx.device.I2C1,
-            (scl, sda),
-            &mut afio.mapr,
-            Mode::Fast {
-                frequency: 100.kHz(),
-                duty_cycle: DutyCycle::Ratio2to1,
-            },
-            clocks,
-            1000,
-            10,
-            1000,
-            1000,
-        );
+        // the timeouts of the `BlockingI2c` count the cycles, without them a missing display
+        // blocks the firmware
+        let mut dcb = cx.core.DCB;
+        dcb.enable_trace();
+        let mut dwt = cx.core.DWT;
+        dwt.enable_cycle_counter();
+        let display = display::connect(cx.device.I2C1, (scl, sda), &mut afio.mapr, clocks);
 
         //-------------------------------------------------------------------------
         //                        rtic initialization
         //-------------------------------------------------------------------------
-        let mut display: GraphicsMode<_> = Builder::new().connect_i2c(i2c).into();
-        display.init().ok();
-        display.flush().ok();
         let systick = cx.core.SYST;
-        // let mono = Systick::new(systick, 36_000_000);
-        let mono = Systick::new(systick, 8_000_000);
+        // NOTE: the Systick counts the core clock, the milliseconds of the timers and the log
+        // depend on it
+        let mono = Systick::new(systick, clocks.sysclk().raw());
 
         let button_up_pin = gpioa.pa5.into_pull_up_input(&mut gpioa.crl);
         let button_down_pin = gpioa.pa6.into_pull_up_input(&mut gpioa.crl);
         let button_enter_pin = gpioa.pa7.into_pull_up_input(&mut gpioa.crl);
+        #[allow(unused_mut)]
+        let mut exti = cx.device.EXTI;
+        #[cfg(feature = "exti")]
+        let (button_up_pin, button_down_pin, button_enter_pin) = (
+            listen(button_up_pin, &mut afio, &mut exti),
+            listen(button_down_pin, &mut afio, &mut exti),
+            listen(button_enter_pin, &mut afio, &mut exti),
+        );
         let mut rtc = Rtc::new(cx.device.RTC, &mut backup_domain);
+        let mut backup = Backup::new(backup_domain);
+        // NOTE: the record of the last panic stays in the backup registers until the next panic
+        let diagnostics = Diagnostics {
+            panic: backup.panic(),
+            errors: ErrorCounters::default(),
+        };
+        // NOTE: the RTC keeps counting with VBAT so the time is only seeded in the first power up
         let today = DateTime {
             year: 2021,
             month: 4,
@@ -161,27 +218,48 @@ mod app {
             hour: 17,
             min: 24,
             sec: 00,
-            day_of_week: datetime::DayOfWeek::Saturday,
+            day_of_week: DayOfWeek::Saturday,
         };
-        if let Some(epoch) = today.to_epoch() {
-            rtc.set_time(epoch);
+        if !backup.is_rtc_initialized() {
+            if let Some(epoch) = today.to_epoch() {
+                rtc.set_time(epoch);
+                backup.set_rtc_initialized();
+            }
         }
 
+        // NOTE: the RTC counts UTC and the time zone converts it to the local time of the screens
+        let timezone = backup.timezone();
+        let alarms: [Alarm; MAX_ALARMS] = core::array::from_fn(|index| backup.alarm(index));
+        schedule_alarm(&mut rtc, &alarms, &timezone);
+        let app_fsm = AppFSM::new(timezone.to_local(rtc.current_time()), timezone, alarms);
         rtc.listen_seconds();
 
         // NOTE(elsuizo:2021-11-24): here we dont need a super fast spawn(for the inititlization...)!!!
-        react::spawn_after(Duration::<u64, 1, 1000>::from_ticks(1000)).unwrap();
+        // NOTE: the queue of `react` is empty so the first spawn can't fail
+        #[cfg(not(feature = "exti"))]
+        react::spawn_after(Duration::<u64, 1, 1000>::from_ticks(1000)).ok();
 
         (
-            Shared { led },
+            Shared {
+                led,
+                exti,
+                rtc,
+                ringing: 0,
+                diagnostics,
+            },
             Local {
                 button_up: Button::new(button_up_pin),
                 button_down: Button::new(button_down_pin),
                 button_enter: Button::new(button_enter_pin),
-                rtc,
                 display,
                 logger,
-                menu_fsm: crate::ui::MenuFSM::init(crate::ui::MenuState::Row1(false)),
+                app_fsm,
+                backup,
+                rx,
+                tx,
+                producer,
+                consumer,
+                log_queue,
             },
             init::Monotonics(mono),
         )
@@ -193,58 +271,448 @@ mod app {
     #[idle]
     fn idle(_: idle::Context) -> ! {
         loop {
+            // with the EXTI input nothing needs to run until a button or a timer wakes us up
+            #[cfg(feature = "exti")]
+            cortex_m::asm::wfi();
             continue;
         }
     }
     // NOTE(elsuizo:2021-11-24): the maximum period of this periodic task for a responsive button
     // action is 13 ms
+    // NOTE: the debounce and the `GestureConfig` of the buttons are counted in periods of this
+    // task, it runs every `POLL_PERIOD_MS` so a press is debounced in 30 ms, a long press is
+    // 600 ms and the repeat 150 ms
     // NOTE(elsuizo:2021-11-21): remember that the method set_low() needs the trait: `use embedded_hal::digital::v2::OutputPin;`
     // to be used!!!
-    #[task(local = [button_up, button_down, button_enter], shared = [led])]
+    /// with the EXTI input the polling only runs from the first edge until the buttons are idle
+    #[task(local = [button_up, button_down, button_enter], shared = [led, exti, diagnostics])]
     fn react(cx: react::Context) {
-        use crate::buttons::PinState::*;
-        use crate::ui::Msg::*;
+        let mut diagnostics = cx.shared.diagnostics;
+        let idle = poll_buttons(
+            cx.local.button_up,
+            cx.local.button_down,
+            cx.local.button_enter,
+            &mut diagnostics,
+        );
+        if cfg!(feature = "exti") && idle {
+            // the next edge of a button restarts the polling, the pending bits are not cleared
+            // so a edge since the last poll is not lost
+            let mut exti = cx.shared.exti;
+            exti.lock(|exti| {
+                exti.imr
+                    .modify(|_, w| w.mr5().unmasked().mr6().unmasked().mr7().unmasked());
+            });
+        } else if react::spawn_after(Duration::<u64, 1, 1000>::from_ticks(POLL_PERIOD_MS.into()))
+            .is_err()
+        {
+            // it only fails if other run is waiting, the polling goes on with it
+            count_error(&mut diagnostics, AppError::Spawn);
+        }
+    }
 
-        if let PinUp = cx.local.button_up.poll() {
-            dispatch_msg::spawn(Up).ok();
+    /// a edge in any button starts the polling, the bounces are ignored until the buttons are
+    /// idle again
+    #[cfg(feature = "exti")]
+    #[task(binds = EXTI9_5, shared = [exti, diagnostics])]
+    fn button_edge(mut cx: button_edge::Context) {
+        cx.shared.exti.lock(|exti| {
+            exti.imr
+                .modify(|_, w| w.mr5().masked().mr6().masked().mr7().masked());
+            exti.pr
+                .write(|w| w.pr5().clear().pr6().clear().pr7().clear());
+        });
+        // NOTE: the edges are masked while `react` polls so its queue is empty
+        if react::spawn().is_err() {
+            count_error(&mut cx.shared.diagnostics, AppError::Spawn);
         }
-        if let PinUp = cx.local.button_down.poll() {
-            dispatch_msg::spawn(Down).ok();
+    }
+
+    /// poll the buttons and send the gestures to the menu, returns true if all are idle
+    fn poll_buttons(
+        button_up: &mut Button<ButtonUpPin>,
+        button_down: &mut Button<ButtonDownPin>,
+        button_enter: &mut Button<ButtonEnterPin>,
+        diagnostics: &mut impl rtic::Mutex<T = Diagnostics>,
+    ) -> bool {
+        use ui::{Key, Msg};
+
+        let gestures = [
+            (Key::Up, button_up.poll_gesture()),
+            (Key::Down, button_down.poll_gesture()),
+            (Key::Enter, button_enter.poll_gesture()),
+        ];
+        for (key, gesture) in gestures {
+            if let Some(gesture) = gesture {
+                dispatch(Event::Msg(Msg::from_gesture(key, gesture)), diagnostics);
+            }
+        }
+        button_up.is_idle() && button_down.is_idle() && button_enter.is_idle()
+    }
+
+    /// send an event to `dispatch_msg`, with its queue full the event is lost and counted
+    fn dispatch(event: Event, diagnostics: &mut impl rtic::Mutex<T = Diagnostics>) {
+        if dispatch_msg::spawn(event).is_err() {
+            count_error(diagnostics, AppError::Spawn);
         }
-        if let PinUp = cx.local.button_enter.poll() {
-            dispatch_msg::spawn(Enter).ok();
+    }
+
+    /// count an error in the diagnostics screen
+    fn count_error(diagnostics: &mut impl rtic::Mutex<T = Diagnostics>, error: AppError) {
+        diagnostics.lock(|diagnostics| diagnostics.errors.count(error));
+    }
+
+    /// make the pin of a button a source of interrupts in both edges
+    #[cfg(feature = "exti")]
+    fn listen<P: gpio::ExtiPin>(
+        mut pin: P,
+        afio: &mut stm32f1xx_hal::afio::Parts,
+        exti: &mut pac::EXTI,
+    ) -> P {
+        pin.make_interrupt_source(afio);
+        pin.trigger_on_edge(exti, gpio::Edge::RisingFalling);
+        pin.enable_interrupt(exti);
+        pin
+    }
+
+    /// the seconds of the RTC redraw the clock screen, the alarm flag starts the alarm
+    #[task(binds = RTC, shared = [rtc, diagnostics])]
+    fn rtc_tick(mut cx: rtc_tick::Context) {
+        let alarm = cx.shared.rtc.lock(|rtc| {
+            rtc.clear_second_flag();
+            rtc.wait_alarm().is_ok()
+        });
+        if alarm && ring_alarm::spawn().is_err() {
+            count_error(&mut cx.shared.diagnostics, AppError::Spawn);
         }
-        react::spawn_after(Duration::<u64, 1, 1000>::from_ticks(30)).unwrap();
+        dispatch(Event::Msg(ui::Msg::Tick), &mut cx.shared.diagnostics);
     }
 
-    #[task(local = [display, logger, menu_fsm, rtc], shared = [led])]
-    fn dispatch_msg(cx: dispatch_msg::Context, msg: crate::ui::Msg) {
-        use crate::ui::Msg::*;
-        let dispatch_msg::SharedResources { mut led } = cx.shared;
-        cx.local.display.clear();
-        cx.local.menu_fsm.next_state(msg);
+    /// show the alarm screen and blink the led until the alarm is stopped or snoozed
+    #[task(shared = [ringing, diagnostics])]
+    fn ring_alarm(mut cx: ring_alarm::Context) {
+        // without the alarm screen nothing would stop the blink
+        if dispatch_msg::spawn(Event::Msg(ui::Msg::Alarm)).is_err() {
+            count_error(&mut cx.shared.diagnostics, AppError::Spawn);
+            return;
+        }
+        cx.shared.ringing.lock(|ringing| *ringing |= RING_ALARM);
+        // NOTE: it only fails if a blink is waiting and that one keeps the led blinking
+        blink::spawn().ok();
+    }
+
+    #[task(shared = [led, ringing])]
+    fn blink(cx: blink::Context) {
+        let blink::SharedResources {
+            mut led,
+            mut ringing,
+        } = cx.shared;
+        if ringing.lock(|ringing| *ringing != 0) {
+            led.lock(|l| l.toggle());
+            // NOTE: it only fails if other blink is waiting, it goes on with the blink
+            blink::spawn_after(Duration::<u64, 1, 1000>::from_ticks(250)).ok();
+        } else {
+            // the led of the Blue Pill is active low
+            led.lock(|l| l.set_high());
+        }
+    }
+
+    /// redraw a running timer, only one redraw is waiting at any time
+    #[task(shared = [diagnostics])]
+    fn refresh(mut cx: refresh::Context) {
+        dispatch(Event::Msg(ui::Msg::Tick), &mut cx.shared.diagnostics);
+    }
+
+    /// the received bytes wait in a queue until the `console` task collects the lines and the
+    /// bytes of the log are sent while the UART is ready. The priority is over the tasks that
+    /// log so they can wait for free space in the log queue
+    #[task(
+        binds = USART1,
+        priority = 2,
+        local = [rx, tx, producer, log_queue],
+        shared = [diagnostics]
+    )]
+    fn usart1(mut cx: usart1::Context) {
+        // the `console` empties the queue in each run, with bytes waiting it is already spawned
+        let waiting = cx.local.producer.len() > 0;
+        let mut received = false;
+        loop {
+            // NOTE: with the queue full the byte is lost and its line fails to parse
+            match cx.local.rx.read() {
+                Ok(byte) => cx.local.producer.enqueue(Ok(byte)).ok(),
+                Err(nb::Error::WouldBlock) => break,
+                // the read clears the error flags, the byte is lost
+                Err(nb::Error::Other(err)) => {
+                    cx.local.producer.enqueue(Err(io::rx_error(err))).ok()
+                }
+            };
+            received = true;
+        }
+        if received && console::spawn().is_err() && !waiting {
+            count_error(&mut cx.shared.diagnostics, AppError::Spawn);
+        }
+        // the `Logger` pends this interrupt after queueing and the TX empty interrupt keeps it
+        // running until the queue is empty
+        let (tx, log_queue) = (cx.local.tx, cx.local.log_queue);
+        while let Some(&byte) = log_queue.peek() {
+            if tx.write(byte).is_err() {
+                break;
+            }
+            log_queue.dequeue();
+        }
+        if log_queue.ready() {
+            tx.listen();
+        } else {
+            tx.unlisten();
+        }
+    }
+
+    /// collect the received bytes in lines and send the commands to `dispatch_msg`, one run
+    /// empties the queue so a failed spawn doesn't lose bytes
+    #[task(local = [consumer, console: Console = Console::new()], shared = [diagnostics])]
+    fn console(cx: console::Context) {
+        let console = cx.local.console;
+        let mut diagnostics = cx.shared.diagnostics;
+        while let Some(received) = cx.local.consumer.dequeue() {
+            match received {
+                Ok(byte) => {
+                    if let Some(command) = console.push(byte) {
+                        dispatch(Event::Command(command), &mut diagnostics);
+                    }
+                }
+                // the line with the lost byte is discarded
+                Err(err) => {
+                    console.clear();
+                    dispatch(Event::SerialError(err), &mut diagnostics);
+                }
+            }
+        }
+    }
+
+    #[task(
+        capacity = 4,
+        local = [
+            display, logger, app_fsm, backup,
+            canvas: Canvas = Canvas::new(),
+            connected: bool = true,
+        ],
+        shared = [led, rtc, ringing, diagnostics]
+    )]
+    fn dispatch_msg(cx: dispatch_msg::Context, event: Event) {
+        use ui::Msg::*;
+        let dispatch_msg::SharedResources {
+            mut led,
+            mut rtc,
+            mut ringing,
+            mut diagnostics,
+        } = cx.shared;
+        let app_fsm = cx.local.app_fsm;
+        let millis = monotonics::now().ticks() as Millis;
+        if app_fsm.poll(millis) {
+            ringing.lock(|ringing| *ringing |= RING_COUNTDOWN);
+            // NOTE: it only fails if a blink is waiting and that one keeps the led blinking
+            blink::spawn().ok();
+            info!(cx.local.logger, target: "timer", "time up!!!").ok();
+        }
+        let msg = match event {
+            Event::Msg(msg) => msg,
+            Event::Command(Ok(Command::Menu(key))) => ui::Msg::from_gesture(key, Gesture::Click),
+            Event::Command(Ok(Command::TimeSet(local))) => match app_fsm.timezone.to_utc(&local) {
+                Some(utc) => {
+                    rtc.lock(|rtc| {
+                        rtc.set_time(utc);
+                        schedule_alarm(rtc, &app_fsm.alarms, &app_fsm.timezone);
+                    });
+                    info!(cx.local.logger, target: "clock", "clock updated").ok();
+                    // redraw the clock with the new time
+                    Tick
+                }
+                None => {
+                    error!(cx.local.logger, target: "console", "invalid local time").ok();
+                    return;
+                }
+            },
+            Event::Command(Ok(command)) => {
+                let now = app_fsm
+                    .timezone
+                    .to_local(rtc.lock(|rtc| rtc.current_time()));
+                run_command(command, cx.local.logger, cx.local.canvas, &now);
+                return;
+            }
+            Event::Command(Err(err)) => {
+                warn!(cx.local.logger, target: "console", "{}", err.description()).ok();
+                return;
+            }
+            Event::SerialError(err) => {
+                count_error(&mut diagnostics, err.into());
+                warn!(cx.local.logger, target: "console", "{}", err.description()).ok();
+                return;
+            }
+        };
+        // the display can be unplugged, the ticks look for it and a display plugged again is
+        // initialized and the whole screen is drawn because it lost the last frame
+        let connected = cx.local.connected;
+        let mut reconnected = false;
+        if msg == Tick && display::is_present(cx.local.display) != *connected {
+            if *connected {
+                *connected = false;
+                count_error(&mut diagnostics, AppError::Display);
+                warn!(cx.local.logger, target: "display", "display disconnected").ok();
+            } else if cx.local.display.init().is_ok() {
+                *connected = true;
+                reconnected = true;
+                diagnostics.lock(|diagnostics| diagnostics.errors.recovered());
+                info!(cx.local.logger, target: "display", "display connected").ok();
+            }
+        }
+        if msg == Tick && !app_fsm.is_animated() && !reconnected {
+            return;
+        }
+        let now = app_fsm
+            .timezone
+            .to_local(rtc.lock(|rtc| rtc.current_time()));
+        cx.local.canvas.clear(BinaryColor::Off).ok();
+        let logger = cx.local.logger;
+        match app_fsm.update(msg, &now, millis) {
+            Some(Effect::Alarm(action)) => {
+                ringing.lock(|ringing| *ringing &= !RING_ALARM);
+                rtc.lock(|rtc| match action {
+                    AlarmAction::Stop => schedule_alarm(rtc, &app_fsm.alarms, &app_fsm.timezone),
+                    AlarmAction::Snooze => {
+                        rtc.set_alarm(rtc.current_time() + SNOOZE_SECONDS);
+                        rtc.listen_alarm();
+                    }
+                });
+            }
+            Some(Effect::SetTime(utc)) => {
+                rtc.lock(|rtc| {
+                    rtc.set_time(utc);
+                    schedule_alarm(rtc, &app_fsm.alarms, &app_fsm.timezone);
+                });
+                info!(logger, target: "clock", "clock updated").ok();
+            }
+            Some(Effect::SetTimeZone(timezone)) => {
+                cx.local.backup.set_timezone(timezone);
+                rtc.lock(|rtc| schedule_alarm(rtc, &app_fsm.alarms, &timezone));
+                info!(logger, target: "clock", "time zone {}", timezone).ok();
+            }
+            Some(Effect::SetAlarm(index, alarm)) => {
+                cx.local.backup.set_alarm(index, alarm);
+                rtc.lock(|rtc| schedule_alarm(rtc, &app_fsm.alarms, &app_fsm.timezone));
+                info!(logger, target: "alarm", "alarm {} updated", index + 1).ok();
+            }
+            Some(Effect::CountdownReset) => ringing.lock(|ringing| *ringing &= !RING_COUNTDOWN),
+            None => {}
+        }
+        if app_fsm.is_running() {
+            refresh::spawn_after(Duration::<u64, 1, 1000>::from_ticks(REFRESH_PERIOD_MS)).ok();
+        }
         match msg {
             Up => {
                 led.lock(|l| l.toggle());
-                cx.local.logger.log("button Up pressed!!!").ok();
-                crate::ui::draw_menu(cx.local.display, cx.local.menu_fsm.state, None).ok();
-                cx.local.display.flush().unwrap();
+                info!(logger, target: "button", "Up pressed!!!").ok();
             }
             Down => {
                 led.lock(|l| l.toggle());
-                cx.local.logger.log("button Down pressed!!!").ok();
-                crate::ui::draw_menu(cx.local.display, cx.local.menu_fsm.state, None).ok();
-                cx.local.display.flush().unwrap();
+                info!(logger, target: "button", "Down pressed!!!").ok();
             }
             Enter => {
                 led.lock(|l| l.toggle());
-                cx.local.logger.log("button Enter pressed!!!").ok();
-                let mut out: String<256> = String::new();
-                let datetime = DateTime::new(cx.local.rtc.current_time());
-                write!(&mut out, "{}", datetime).unwrap();
-                crate::ui::draw_menu(cx.local.display, cx.local.menu_fsm.state, Some(&out)).ok();
-                cx.local.display.flush().unwrap();
+                info!(logger, target: "button", "Enter pressed!!!").ok();
+            }
+            LongPress(_) | DoubleClick(_) | Repeat(_) => {
+                led.lock(|l| l.toggle());
+                info!(logger, target: "button", "{:?}", msg).ok();
             }
+            Alarm => {
+                info!(logger, target: "alarm", "alarm!!!").ok();
+            }
+            Tick => {}
+        };
+        let (display, canvas) = (cx.local.display, cx.local.canvas);
+        let overflows = logger.overflows();
+        // NOTE: the USART1 shares the diagnostics, so the lock only copies them and the
+        // draw and the slow transfer to the display run without masking its interrupt
+        let snapshot = diagnostics.lock(|diagnostics| {
+            diagnostics.errors.log_overflows = overflows;
+            diagnostics.clone()
+        });
+        app_fsm.draw(canvas, &now, millis, &snapshot, msg).ok();
+        let shown = match *connected {
+            true => flush(display, canvas),
+            false => Ok(false),
         };
+        diagnostics.lock(|diagnostics| match shown {
+            Ok(true) => {
+                diagnostics.errors.count(AppError::Display);
+                diagnostics.errors.recovered();
+            }
+            Ok(false) => {}
+            Err(err) => diagnostics.errors.count(err),
+        });
+        // NOTE: the ticks look for the display until it answers again
+        if let Err(err) = shown {
+            *connected = false;
+            warn!(logger, target: "display", "{}", err).ok();
+        }
+    }
+
+    /// the commands of the console that only answer, the others are handled like the buttons
+    fn run_command(command: Command, logger: &mut Logger, canvas: &Canvas, now: &DateTime) {
+        match command {
+            Command::Help => {
+                logger.send(HELP.as_bytes()).ok();
+                logger.send(b"\r\n").ok();
+            }
+            Command::TimeGet => {
+                info!(logger, target: "console", "{}", now).ok();
+            }
+            // the last frame, one line of hex digits for each row of pixels and the first pixel
+            // is the high bit
+            Command::ScreenDump => {
+                info!(logger, target: "console", "screen 128x64").ok();
+                for row in canvas.rows() {
+                    let mut line: String<{ 2 * ROW_BYTES + 2 }> = String::new();
+                    for byte in row {
+                        write!(&mut line, "{:02X}", byte).ok();
+                    }
+                    line.push_str("\r\n").ok();
+                    logger.send(line.as_bytes()).ok();
+                }
+            }
+            Command::LogLevel(level) => {
+                logger.set_level(level);
+                info!(logger, target: "console", "log level {}", logger.level().name()).ok();
+            }
+            Command::TimeSet(_) | Command::Menu(_) => {}
+        }
+    }
+
+    /// show a frame in the display, a failed transfer is tried again after initializing the
+    /// display because it loses the configuration in a brownout or a reconnection. Returns true
+    /// when the display was recovered after a failed transfer
+    fn flush(display: &mut OledDisplay, canvas: &Canvas) -> Result<bool, AppError> {
+        display::copy(display, canvas);
+        if display.flush().is_ok() {
+            return Ok(false);
+        }
+        // NOTE: the failed transfer already reset the I2C1
+        for _ in 0..DISPLAY_RETRIES {
+            if display.init().and_then(|_| display.flush()).is_ok() {
+                return Ok(true);
+            }
+        }
+        Err(AppError::Display)
+    }
+
+    /// set the RTC alarm to the next of the `alarms`, they are in the local time of `timezone`
+    fn schedule_alarm(rtc: &mut Rtc, alarms: &[Alarm], timezone: &TimeZone) {
+        let now = timezone.to_local(rtc.current_time());
+        match next_alarm(alarms, &now).and_then(|local| timezone.to_utc(&local)) {
+            Some(utc) => {
+                rtc.set_alarm(utc);
+                rtc.listen_alarm();
+            }
+            None => rtc.unlisten_alarm(),
+        }
     }
 }
@@ -0,0 +1,123 @@
+/// The panic handler: it saves the panic for the next boot, logs it and shows it in the display.
+/// The peripherals are owned by the tasks so they are stolen, nothing else runs after a panic
+#[cfg(not(feature = "rtt"))]
+use core::convert::Infallible;
+use core::fmt::Write;
+use core::panic::PanicInfo;
+use core::sync::atomic::{self, AtomicBool, Ordering};
+
+use heapless::String;
+use rtic_oled_ui_core::diagnostics::draw_panic;
+use rtic_oled_ui_core::timers::Millis;
+use rtic_oled_ui_core::{error, log, Canvas};
+use stm32f1xx_hal::{pac, prelude::*};
+
+use crate::{backup, display};
+
+/// a panic inside the handler only stops the board
+static PANICKING: AtomicBool = AtomicBool::new(false);
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    cortex_m::interrupt::disable();
+    if !PANICKING.swap(true, Ordering::Relaxed) {
+        report(info);
+    }
+    // NOTE: the panic stays in the display until the reset
+    loop {
+        atomic::compiler_fence(Ordering::SeqCst);
+    }
+}
+
+/// each step is best effort, the record is first because it can't fail
+fn report(info: &PanicInfo) {
+    let (file, line) = info
+        .location()
+        .map_or(("?", 0), |location| (location.file(), location.line()));
+    let record = backup::save_panic(file, line);
+    let mut location: String<32> = String::new();
+    let name = file.rsplit('/').next().unwrap_or(file);
+    write!(&mut location, "{}:{}", name, line).ok();
+    let mut message: String<{ log::MAX_LINE_LEN }> = String::new();
+    write!(&mut message, "{}", info.message()).ok();
+    if let Some(port) = log_port() {
+        let mut logger = log::Logger::new(port, || crate::app::monotonics::now().ticks() as Millis);
+        error!(logger, target: "panic", "{} (#{}) {}", location, record.count, message).ok();
+    }
+    show(&location, &message);
+}
+
+/// draw the panic in a new connection with the display, it resets the GPIOB so it is the last
+/// step
+fn show(location: &str, message: &str) {
+    let mut canvas = Canvas::new();
+    draw_panic(&mut canvas, location, message).ok();
+    // SAFETY: the handler doesn't return so the tasks don't use the peripherals anymore
+    let (dp, mut cp) = unsafe { (pac::Peripherals::steal(), cortex_m::Peripherals::steal()) };
+    // the timeouts of the `BlockingI2c` count the cycles, with a missing display it doesn't block
+    cp.DCB.enable_trace();
+    cp.DWT.enable_cycle_counter();
+    let rcc = dp.RCC.constrain();
+    let mut flash = dp.FLASH.constrain();
+    let clocks = crate::clocks(rcc.cfgr, &mut flash.acr);
+    let mut afio = dp.AFIO.constrain();
+    let mut gpiob = dp.GPIOB.split();
+    let scl = gpiob.pb8.into_alternate_open_drain(&mut gpiob.crh);
+    let sda = gpiob.pb9.into_alternate_open_drain(&mut gpiob.crh);
+    let mut display = display::connect(dp.I2C1, (scl, sda), &mut afio.mapr, clocks);
+    display::copy(&mut display, &canvas);
+    display.flush().ok();
+}
+
+//-------------------------------------------------------------------------
+//                        log ports
+//-------------------------------------------------------------------------
+/// the USART1 if the `init` enabled it, the `Tx` is owned by a task that can't run anymore
+#[cfg(not(feature = "rtt"))]
+fn log_port() -> Option<UartPort> {
+    // SAFETY: the handler doesn't return so the `usart1` task doesn't use the registers anymore
+    let usart = unsafe { &*pac::USART1::ptr() };
+    usart
+        .cr1
+        .read()
+        .ue()
+        .bit_is_set()
+        .then_some(UartPort(usart))
+}
+
+/// the up channel of the log, if the `init` created it
+#[cfg(feature = "rtt")]
+fn log_port() -> Option<crate::io::RttPort> {
+    // SAFETY: the handler doesn't return so the `Logger` doesn't use the channel anymore
+    unsafe { rtt_target::UpChannel::conjure(0) }.map(crate::io::RttPort)
+}
+
+/// The USART1 without the queue of the log, it waits for the UART because nothing else runs
+#[cfg(not(feature = "rtt"))]
+struct UartPort(&'static pac::usart1::RegisterBlock);
+
+#[cfg(not(feature = "rtt"))]
+impl embedded_hal::serial::Write<u8> for UartPort {
+    type Error = Infallible;
+
+    fn write(&mut self, byte: u8) -> nb::Result<(), Infallible> {
+        while self.0.sr.read().txe().bit_is_clear() {}
+        self.0.dr.write(|w| w.dr().bits(u16::from(byte)));
+        Ok(())
+    }
+
+    /// the GPIOB of the UART is reset after the log, the last byte must be sent
+    fn flush(&mut self) -> nb::Result<(), Infallible> {
+        while self.0.sr.read().tc().bit_is_clear() {}
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "rtt"))]
+impl log::WriteFrame for UartPort {
+    fn write_frame(&mut self, frame: &[u8]) -> nb::Result<(), Infallible> {
+        frame
+            .iter()
+            .try_for_each(|&byte| embedded_hal::serial::Write::write(self, byte))
+    }
+}